@@ -1,6 +1,5 @@
 extern crate letsbayes;
-use emcee;
-use statrs::distribution::{Normal, Uniform};
+use statrs::distribution::Uniform;
 
 fn main() {
     println!("Influence function example...");
@@ -1,7 +1,5 @@
 extern crate letsbayes;
-use emcee;
-use polars::{error::constants::TRUE, prelude::*};
-use statrs::distribution::{Normal, Uniform};
+use statrs::distribution::Uniform;
 
 fn main() {
     println!("Influence function from input file example...");
@@ -14,68 +12,18 @@ fn main() {
             distribution: Uniform::new(0.0, 100.0).unwrap(),
         }),
     ]);
-    let df_csv = CsvReadOptions::default()
-        .with_infer_schema_length(None)
-        .with_has_header(true)
-        .try_into_reader_with_file_path(Some(
-            "examples/ocean_influence_function/inf_inputs.csv".into(),
-        ))
-        .unwrap()
-        .finish()
-        .unwrap();
-    println!("{}", df_csv);
-    let tmp = df_csv
-        .clone()
-        .select_series(["influence_function_1", "influence_function_2"])
-        .unwrap();
-
-    let vec1: Vec<f64> = tmp[0].f64().unwrap().into_no_null_iter().collect();
-    let vec2: Vec<f64> = tmp[1].f64().unwrap().into_no_null_iter().collect();
-
-    println!("{:?}, {:?}", vec1, vec2);
-
-    let influence = letsbayes::models::InfluenceFunction::new(vec![vec1, vec2], 0.0);
-    // regular least squares evidence
-
-    let obs_values: Vec<f64> = df_csv
-        .clone()
-        .lazy()
-        .filter(col("is_nondetect?").eq(false))
-        .collect()
-        .unwrap()
-        .select_series(["observed concentration"])
-        .unwrap()[0]
-        .f64()
-        .unwrap()
-        .into_no_null_iter()
-        .collect();
-    let nd_values: Vec<f64> = df_csv
-        .clone()
-        .lazy()
-        .filter(col("is_nondetect?").eq(true))
-        .collect()
-        .unwrap()
-        .select_series(["detection limit"])
-        .unwrap()[0]
-        .f64()
-        .unwrap()
-        .into_no_null_iter()
-        .collect();
-
-    println!("{:?}, {:?}", obs_values, nd_values);
-    let mut obs: Vec<Box<dyn letsbayes::likelihood::PartialLikelihood>> = vec![];
-    for v in obs_values {
-        obs.push(Box::new(letsbayes::likelihood::Observation::new(v, 0.3)))
-    }
-    for v in nd_values {
-        obs.push(Box::new(letsbayes::likelihood::NondetectObservation::new(
-            v, 0.0,
-        )))
-    }
-    let obs = letsbayes::likelihood::ObservationSet::new(obs);
+    // observations with per-row uncertainties, and one influence function
+    // per source
+    let data = letsbayes::data::read_influence_csv(
+        "examples/ocean_influence_function/inf_inputs.csv",
+        0.0,
+    )
+    .expect("could not read input data");
+    println!("sources: {:?}", data.sources);
 
     // make inference problem
-    let problem = letsbayes::InferenceProblem::new_unnamed(prior, obs, influence, 2);
+    let problem =
+        letsbayes::InferenceProblem::new(prior, data.observations, data.model, data.sources);
 
     // sample posterior and write to file
     let posterior = problem.sample(100000, 8);
@@ -1,5 +1,4 @@
 extern crate letsbayes;
-use emcee;
 use statrs::distribution::{Normal, Uniform};
 
 fn main() {
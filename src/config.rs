@@ -0,0 +1,427 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{read_influence_csv_with, DataError, InfluenceCsvOptions, UncertaintyKind};
+pub use crate::data::{DetectLikelihood, NondetectLikelihood};
+use crate::diagnostics;
+use crate::likelihood::ObservationSet;
+use crate::models::{InfluenceFunction, InfluenceFunctionLog, Model};
+use crate::posterior::Posterior;
+use crate::priors::{BasicPrior, PartialPriorSpec};
+use crate::InferenceProblem;
+
+/// A complete inference run read from a JSON file: input data, priors,
+/// likelihood, model, sampler settings and outputs. Relative paths are taken
+/// relative to the configuration file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub data: DataConfig,
+    pub parameters: Vec<ParameterConfig>,
+    #[serde(default)]
+    pub likelihood: LikelihoodConfig,
+    pub model: ModelConfig,
+    pub sampler: SamplerConfig,
+    pub output: OutputConfig,
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+/// Which columns of the input CSV hold each quantity. Without a
+/// `nondetect_column` every row is treated as a detect. `uncertainty` says
+/// whether the uncertainty column is absolute or relative. The identifier and
+/// `metadata_columns` are carried through to the diagnostics output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataConfig {
+    pub path: PathBuf,
+    pub value_column: String,
+    pub uncertainty_column: String,
+    #[serde(default)]
+    pub uncertainty: UncertaintyKind,
+    #[serde(default)]
+    pub nondetect_column: Option<String>,
+    #[serde(default)]
+    pub detection_limit_column: Option<String>,
+    #[serde(default)]
+    pub sample_id_column: Option<String>,
+    #[serde(default)]
+    pub cruise_id_column: Option<String>,
+    #[serde(default)]
+    pub metadata_columns: Vec<String>,
+}
+
+/// A named parameter, the CSV column holding its influence function, and
+/// its prior.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterConfig {
+    pub name: String,
+    pub influence_column: String,
+    pub prior: PartialPriorSpec,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LikelihoodConfig {
+    #[serde(default)]
+    pub detects: DetectLikelihood,
+    #[serde(default)]
+    pub nondetects: NondetectLikelihood,
+}
+
+/// `InfluenceFunctionLog` predicts log10 concentrations, so the value column
+/// must then hold log10 values too.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ModelConfig {
+    InfluenceFunction {
+        #[serde(default)]
+        relative_error: f64,
+    },
+    InfluenceFunctionLog {
+        #[serde(default)]
+        relative_error: f64,
+    },
+}
+
+/// Sampler settings. `burn_in` samples are dropped and every `thinning`-th
+/// sample is kept when writing the posterior.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplerConfig {
+    pub iterations: usize,
+    pub walkers_per_dim: usize,
+    #[serde(default)]
+    pub burn_in: usize,
+    #[serde(default = "default_thinning")]
+    pub thinning: usize,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_thinning() -> usize {
+    1
+}
+
+/// Output files. `problem` saves the assembled `InferenceProblem` as JSON
+/// and `diagnostics` the per-observation fit as CSV.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    pub posterior: PathBuf,
+    #[serde(default)]
+    pub problem: Option<PathBuf>,
+    #[serde(default)]
+    pub diagnostics: Option<PathBuf>,
+}
+
+/// Problems found while reading a configuration or its input data.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Data(DataError),
+    /// A prior or other setting is invalid.
+    Invalid(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Json(e) => write!(f, "invalid configuration: {}", e),
+            ConfigError::Data(e) => write!(f, "{}", e),
+            ConfigError::Invalid(message) => write!(f, "invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        ConfigError::Json(e)
+    }
+}
+
+impl From<DataError> for ConfigError {
+    fn from(e: DataError) -> Self {
+        ConfigError::Data(e)
+    }
+}
+
+impl RunConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let mut config: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.base_dir.join(path)
+    }
+
+    /// Where the posterior samples are written.
+    pub fn posterior_path(&self) -> PathBuf {
+        self.resolve(&self.output.posterior)
+    }
+
+    pub fn parameter_names(&self) -> Vec<String> {
+        self.parameters.iter().map(|p| p.name.clone()).collect()
+    }
+
+    pub fn prior(&self) -> Result<BasicPrior, ConfigError> {
+        let partial_priors = self
+            .parameters
+            .iter()
+            .map(|p| {
+                p.prior
+                    .clone()
+                    .build()
+                    .map_err(|e| ConfigError::Invalid(format!("prior for '{}': {}", p.name, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(BasicPrior::new(partial_priors))
+    }
+
+    /// Read the input CSV into one observation per row, with its provenance,
+    /// and one influence function per parameter, both in row order.
+    pub fn load_data(&self) -> Result<(ObservationSet, Vec<Vec<f64>>), ConfigError> {
+        let options = InfluenceCsvOptions {
+            value_column: self.data.value_column.clone(),
+            uncertainty_column: self.data.uncertainty_column.clone(),
+            nondetect_column: self.data.nondetect_column.clone(),
+            detection_limit_column: self.data.detection_limit_column.clone(),
+            sample_id_column: self.data.sample_id_column.clone(),
+            cruise_id_column: self.data.cruise_id_column.clone(),
+            metadata_columns: Some(self.data.metadata_columns.clone()),
+            influence_columns: Some(
+                self.parameters
+                    .iter()
+                    .map(|p| p.influence_column.clone())
+                    .collect(),
+            ),
+            uncertainty: self.data.uncertainty,
+            detects: self.likelihood.detects,
+            nondetects: self.likelihood.nondetects,
+        };
+        let data = read_influence_csv_with(self.resolve(&self.data.path), 0.0, &options)?;
+        Ok((data.observations, data.model.weights().to_vec()))
+    }
+
+    /// Assemble the problem, sample it and write the outputs.
+    pub fn run(&self) -> Result<Posterior, ConfigError> {
+        self.check_sampler()?;
+        let (observations, weights) = self.load_data()?;
+        match self.model {
+            ModelConfig::InfluenceFunction { relative_error } => self.run_model(
+                observations,
+                InfluenceFunction::new(weights, relative_error),
+            ),
+            ModelConfig::InfluenceFunctionLog { relative_error } => self.run_model(
+                observations,
+                InfluenceFunctionLog::new(weights, relative_error),
+            ),
+        }
+    }
+
+    /// The sampler needs an even number of walkers, more than twice the
+    /// number of parameters, and at least one iteration.
+    fn check_sampler(&self) -> Result<(), ConfigError> {
+        let sampler = &self.sampler;
+        let invalid = |message: &str| Err(ConfigError::Invalid(message.to_string()));
+        if sampler.iterations == 0 {
+            return invalid("iterations must be at least 1");
+        }
+        if sampler.walkers_per_dim < 3 {
+            return invalid("walkers_per_dim must be at least 3");
+        }
+        if !(sampler.walkers_per_dim * self.parameters.len()).is_multiple_of(2) {
+            return invalid("walkers_per_dim times the number of parameters must be even");
+        }
+        if sampler.thinning == 0 {
+            return invalid("thinning must be at least 1");
+        }
+        Ok(())
+    }
+
+    fn run_model<M: Model + Serialize>(
+        &self,
+        observations: ObservationSet,
+        model: M,
+    ) -> Result<Posterior, ConfigError> {
+        let problem =
+            InferenceProblem::try_new(self.prior()?, observations, model, self.parameter_names())
+                .map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        if let Some(path) = &self.output.problem {
+            std::fs::write(self.resolve(path), serde_json::to_string_pretty(&problem)?)?;
+        }
+        let sampler = &self.sampler;
+        let posterior = match sampler.seed {
+            Some(seed) => problem.sample_seeded(sampler.iterations, sampler.walkers_per_dim, seed),
+            None => problem.sample(sampler.iterations, sampler.walkers_per_dim),
+        };
+        posterior.to_csv(
+            &self.output_path(&self.output.posterior, "posterior")?,
+            sampler.burn_in,
+            sampler.thinning,
+        )?;
+        if let Some(path) = &self.output.diagnostics {
+            let diagnostics = match sampler.seed {
+                Some(seed) => problem.observation_diagnostics_seeded(
+                    &posterior,
+                    sampler.burn_in,
+                    sampler.thinning,
+                    seed,
+                ),
+                None => {
+                    problem.observation_diagnostics(&posterior, sampler.burn_in, sampler.thinning)
+                }
+            };
+            diagnostics::to_csv(
+                &diagnostics.map_err(|e| ConfigError::Invalid(e.to_string()))?,
+                &self.output_path(path, "diagnostics")?,
+            )?;
+        }
+        Ok(posterior)
+    }
+
+    fn output_path(&self, path: &Path, name: &str) -> Result<String, ConfigError> {
+        self.resolve(path)
+            .into_os_string()
+            .into_string()
+            .map_err(|_| ConfigError::Invalid(format!("{} path is not valid UTF-8", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_run(dir: &Path, csv: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("data.csv"), csv).unwrap();
+        let config = r#"{
+            "data": {
+                "path": "data.csv",
+                "value_column": "value",
+                "uncertainty_column": "uncertainty",
+                "nondetect_column": "nondetect",
+                "detection_limit_column": "limit",
+                "sample_id_column": "sample"
+            },
+            "parameters": [
+                {"name": "a", "influence_column": "influence_function_1",
+                 "prior": {"type": "Independent",
+                           "distribution": {"type": "Uniform", "min": 0.0, "max": 100.0}}},
+                {"name": "b", "influence_column": "influence_function_2",
+                 "prior": {"type": "Truncated", "lower": 0.0,
+                           "distribution": {"type": "Normal", "mean": 10.0, "std_dev": 20.0}}}
+            ],
+            "likelihood": {"nondetects": "LeftCensored"},
+            "model": {"type": "InfluenceFunction"},
+            "sampler": {"iterations": 200, "walkers_per_dim": 4, "burn_in": 100, "seed": 3},
+            "output": {"posterior": "posterior.csv", "problem": "problem.json",
+                       "diagnostics": "diagnostics.csv"}
+        }"#;
+        let path = dir.join("run.json");
+        std::fs::write(&path, config).unwrap();
+        path
+    }
+
+    #[test]
+    fn run_from_config() {
+        let dir = std::env::temp_dir().join(format!("letsbayes-config-{}", std::process::id()));
+        let csv =
+            "sample,value,uncertainty,nondetect,limit,influence_function_1,influence_function_2\n\
+                   S1,12.0,1.0,FALSE,5.0,1.0,0.5\n\
+                   S2,,1.0,TRUE,5.0,0.1,0.1\n\
+                   S3,30.0,2.0,FALSE,5.0,2.0,1.0\n";
+        let config = RunConfig::from_file(write_run(&dir, csv)).unwrap();
+
+        let (observations, weights) = config.load_data().unwrap();
+        assert_eq!(observations.observations.len(), 3);
+        assert_eq!(weights, vec![vec![1.0, 0.1, 2.0], vec![0.5, 0.1, 1.0]]);
+
+        let posterior = config.run().unwrap();
+        assert_eq!(posterior.parameter_names(), ["a", "b"]);
+        let written = std::fs::read_to_string(dir.join("posterior.csv")).unwrap();
+        assert!(written.starts_with("a,b\n"));
+        assert!(dir.join("problem.json").exists());
+        let diagnostics = std::fs::read_to_string(dir.join("diagnostics.csv")).unwrap();
+        let rows: Vec<&str> = diagnostics.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with("S1,,12,"));
+        assert!(rows[2].starts_with("S2,,5,"));
+        // the seed makes runs reproducible
+        let again = config.run().unwrap();
+        assert_eq!(
+            posterior.parameter_samples("a"),
+            again.parameter_samples("a")
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("diagnostics.csv")).unwrap(),
+            diagnostics
+        );
+
+        let missing = "value,uncertainty,nondetect,limit,influence_function_1\n1.0,1.0,0,1.0,1.0\n";
+        let config = RunConfig::from_file(write_run(&dir, missing)).unwrap();
+        let err = config.load_data().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "input data has no column 'influence_function_2'"
+        );
+        // uncertainties are checked like any other input data
+        let negative = "sample,value,uncertainty,nondetect,limit,influence_function_1,\
+                        influence_function_2\nS1,12.0,-1.0,FALSE,5.0,1.0,0.5\n";
+        let config = RunConfig::from_file(write_run(&dir, negative)).unwrap();
+        let err = config.load_data().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "column 'uncertainty' must be positive in row 1"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let dir = std::env::temp_dir().join(format!("letsbayes-invalid-{}", std::process::id()));
+        let csv =
+            "sample,value,uncertainty,nondetect,limit,influence_function_1,influence_function_2
+                   S1,12.0,1.0,FALSE,5.0,1.0,0.5
+";
+        let path = write_run(&dir, csv);
+        let run_with = |change: &dyn Fn(&mut RunConfig)| {
+            let mut config = RunConfig::from_file(&path).unwrap();
+            change(&mut config);
+            config.run().err().map(|e| e.to_string())
+        };
+        assert_eq!(
+            run_with(&|c| c.sampler.iterations = 0).unwrap(),
+            "invalid configuration: iterations must be at least 1"
+        );
+        assert_eq!(
+            run_with(&|c| c.sampler.walkers_per_dim = 0).unwrap(),
+            "invalid configuration: walkers_per_dim must be at least 3"
+        );
+        let odd = |c: &mut RunConfig| {
+            c.sampler.walkers_per_dim = 3;
+            c.parameters.truncate(1);
+        };
+        assert!(run_with(&odd).unwrap().contains("must be even"));
+        // bad prior parameters are reported rather than panicking
+        let bad_prior = |c: &mut RunConfig| {
+            c.parameters[0].prior = PartialPriorSpec::LogUniform {
+                lower: -1.0,
+                upper: 1.0,
+            }
+        };
+        assert!(run_with(&bad_prior)
+            .unwrap()
+            .starts_with("invalid configuration: prior for 'a': invalid prior"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
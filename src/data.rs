@@ -0,0 +1,656 @@
+use std::path::Path;
+
+use polars::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::likelihood::{
+    CauchyObservation, LeftCensoredObservation, NondetectObservation, Observation,
+    ObservationError, ObservationSet, PartialLikelihood, Provenance, StudentTObservation,
+};
+use crate::models::InfluenceFunction;
+
+pub const SAMPLE_ID: &str = "sample_id";
+pub const CRUISE_ID: &str = "cruise_id";
+pub const NONDETECT: &str = "is_nondetect?";
+pub const OBSERVED: &str = "observed concentration";
+pub const DETECTION_LIMIT: &str = "detection limit";
+pub const UNCERTAINTY: &str = "observation uncertainty";
+/// Prefix of the numbered influence function columns, one per source.
+pub const INFLUENCE_PREFIX: &str = "influence_function_";
+
+/// Problems found while reading observation data.
+#[derive(Debug)]
+pub enum DataError {
+    Csv(PolarsError),
+    MissingColumn(String),
+    /// A column has the wrong type or name.
+    InvalidColumn {
+        column: String,
+        reason: String,
+    },
+    /// A cell is empty or out of range. Rows are numbered from 1, excluding
+    /// the header.
+    InvalidCell {
+        column: String,
+        row: usize,
+        reason: String,
+    },
+    NoRows,
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::Csv(e) => write!(f, "could not read input data: {}", e),
+            DataError::MissingColumn(column) => {
+                write!(f, "input data has no column '{}'", column)
+            }
+            DataError::InvalidColumn { column, reason } => {
+                write!(f, "column '{}' {}", column, reason)
+            }
+            DataError::InvalidCell {
+                column,
+                row,
+                reason,
+            } => write!(f, "column '{}' {} in row {}", column, reason, row),
+            DataError::NoRows => write!(f, "input data has no rows"),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<PolarsError> for DataError {
+    fn from(e: PolarsError) -> Self {
+        DataError::Csv(e)
+    }
+}
+
+/// Influence-function model and observations read from one CSV file.
+pub struct InfluenceData {
+    pub model: InfluenceFunction,
+    pub observations: ObservationSet,
+    /// The influence function columns in source order, e.g. for use as
+    /// parameter names.
+    pub sources: Vec<String>,
+}
+
+/// Likelihood used for rows that are detects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DetectLikelihood {
+    #[default]
+    Gaussian,
+    StudentT {
+        degrees_of_freedom: f64,
+    },
+    Cauchy,
+}
+
+/// Likelihood used for rows that are nondetects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum NondetectLikelihood {
+    /// `NondetectObservation`
+    #[default]
+    Nondetect,
+    /// `LeftCensoredObservation`
+    LeftCensored,
+}
+
+/// Whether the uncertainty column holds absolute errors or errors as a
+/// fraction of the value, or of the detection limit for nondetects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum UncertaintyKind {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+/// Which columns hold each quantity and how rows become observations. The
+/// default is the fixed input format described at `read_influence_csv`.
+/// Without a `nondetect_column` every row is a detect, and a
+/// `detection_limit_column` is only needed if some row is a nondetect.
+#[derive(Debug, Clone)]
+pub struct InfluenceCsvOptions {
+    pub value_column: String,
+    pub uncertainty_column: String,
+    pub nondetect_column: Option<String>,
+    pub detection_limit_column: Option<String>,
+    pub sample_id_column: Option<String>,
+    pub cruise_id_column: Option<String>,
+    /// Columns kept as provenance metadata. `None` keeps every column not
+    /// used otherwise.
+    pub metadata_columns: Option<Vec<String>>,
+    /// Influence function columns in source order. `None` uses the
+    /// `influence_function_N` columns ordered by `N`.
+    pub influence_columns: Option<Vec<String>>,
+    pub uncertainty: UncertaintyKind,
+    pub detects: DetectLikelihood,
+    pub nondetects: NondetectLikelihood,
+}
+
+impl Default for InfluenceCsvOptions {
+    fn default() -> Self {
+        Self {
+            value_column: OBSERVED.to_string(),
+            uncertainty_column: UNCERTAINTY.to_string(),
+            nondetect_column: Some(NONDETECT.to_string()),
+            detection_limit_column: Some(DETECTION_LIMIT.to_string()),
+            sample_id_column: Some(SAMPLE_ID.to_string()),
+            cruise_id_column: Some(CRUISE_ID.to_string()),
+            metadata_columns: None,
+            influence_columns: None,
+            uncertainty: UncertaintyKind::default(),
+            detects: DetectLikelihood::default(),
+            nondetects: NondetectLikelihood::default(),
+        }
+    }
+}
+
+/// Read the influence-function input format: one row per sample with the
+/// columns `is_nondetect?`, `observed concentration`, `detection limit`,
+/// `observation uncertainty` and `influence_function_1` onwards. Detects
+/// become `Observation`s and nondetects `NondetectObservation`s at the
+/// detection limit, both with the row's absolute uncertainty. Observations
+/// keep the row order of the file, and carry the optional `sample_id` and
+/// `cruise_id` columns and any other columns as provenance metadata. Use
+/// `read_influence_csv_with` for other column names or likelihoods, e.g.
+/// `LeftCensoredObservation`s for nondetects.
+pub fn read_influence_csv(
+    path: impl AsRef<Path>,
+    relative_error: f64,
+) -> Result<InfluenceData, DataError> {
+    let df = read_csv(path.as_ref())?;
+    // the identifier columns of this format are optional
+    let options = InfluenceCsvOptions {
+        sample_id_column: optional(&df, SAMPLE_ID),
+        cruise_id_column: optional(&df, CRUISE_ID),
+        ..InfluenceCsvOptions::default()
+    };
+    influence_data(&df, relative_error, &options)
+}
+
+/// Read an influence-function CSV with the given column names and
+/// likelihoods. Columns named in `options` must exist.
+pub fn read_influence_csv_with(
+    path: impl AsRef<Path>,
+    relative_error: f64,
+    options: &InfluenceCsvOptions,
+) -> Result<InfluenceData, DataError> {
+    influence_data(&read_csv(path.as_ref())?, relative_error, options)
+}
+
+fn optional(df: &DataFrame, name: &str) -> Option<String> {
+    df.column(name).is_ok().then(|| name.to_string())
+}
+
+fn influence_data(
+    df: &DataFrame,
+    relative_error: f64,
+    options: &InfluenceCsvOptions,
+) -> Result<InfluenceData, DataError> {
+    if df.height() == 0 {
+        return Err(DataError::NoRows);
+    }
+    let sources = match &options.influence_columns {
+        Some(columns) => columns.clone(),
+        None => influence_columns(df)?,
+    };
+    let weights = sources
+        .iter()
+        .map(|name| {
+            let column = float_column(df, name)?;
+            match column.iter().position(|w| !w.is_finite()) {
+                Some(row) => Err(invalid_cell(name, row, "is not finite")),
+                None => Ok(column),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    let nondetects = match &options.nondetect_column {
+        Some(name) => bool_column(df, name)?,
+        None => vec![false; df.height()],
+    };
+    // values are only needed for detects and limits for nondetects
+    let values = float_cells(df, &options.value_column)?;
+    let limits = match &options.detection_limit_column {
+        Some(name) => float_cells(df, name)?,
+        None => match nondetects.iter().position(|x| *x) {
+            Some(row) => {
+                let column = options.nondetect_column.as_deref().unwrap_or_default();
+                return Err(invalid_cell(
+                    column,
+                    row,
+                    "is a nondetect without a detection limit column",
+                ));
+            }
+            None => vec![None; df.height()],
+        },
+    };
+    let uncertainties = float_column(df, &options.uncertainty_column)?;
+
+    let mut observations: Vec<Box<dyn PartialLikelihood>> = vec![];
+    for (row, nondetect) in nondetects.into_iter().enumerate() {
+        let error = uncertainties[row];
+        if !(error > 0.0 && error.is_finite()) {
+            return Err(invalid_cell(
+                &options.uncertainty_column,
+                row,
+                "must be positive",
+            ));
+        }
+        let error = match options.uncertainty {
+            UncertaintyKind::Absolute => ObservationError::Absolute(error),
+            UncertaintyKind::Relative => ObservationError::Relative(error),
+        };
+        let (column, value) = if nondetect {
+            // a nondetect row implies the limit column was given
+            (
+                options
+                    .detection_limit_column
+                    .as_deref()
+                    .unwrap_or_default(),
+                limits[row],
+            )
+        } else {
+            (options.value_column.as_str(), values[row])
+        };
+        let value = value.ok_or_else(|| empty(column, row))?;
+        if !value.is_finite() {
+            return Err(invalid_cell(column, row, "is not finite"));
+        }
+        if error.absolute(value) <= 0.0 {
+            return Err(invalid_cell(
+                &options.uncertainty_column,
+                row,
+                "is relative to a zero value",
+            ));
+        }
+        observations.push(if nondetect {
+            match options.nondetects {
+                NondetectLikelihood::Nondetect => {
+                    Box::new(NondetectObservation::from_error(value, error))
+                }
+                NondetectLikelihood::LeftCensored => {
+                    Box::new(LeftCensoredObservation::from_error(value, error))
+                }
+            }
+        } else {
+            match options.detects {
+                DetectLikelihood::Gaussian => Box::new(Observation::from_error(value, error)),
+                DetectLikelihood::StudentT { degrees_of_freedom } => Box::new(
+                    StudentTObservation::from_error(value, error, degrees_of_freedom),
+                ),
+                DetectLikelihood::Cauchy => Box::new(CauchyObservation::from_error(value, error)),
+            }
+        });
+    }
+
+    let metadata: Vec<String> = match &options.metadata_columns {
+        Some(columns) => columns.clone(),
+        None => {
+            let used: Vec<&str> = [
+                Some(options.value_column.as_str()),
+                Some(options.uncertainty_column.as_str()),
+                options.nondetect_column.as_deref(),
+                options.detection_limit_column.as_deref(),
+                options.sample_id_column.as_deref(),
+                options.cruise_id_column.as_deref(),
+                // the standard identifier columns are never metadata
+                Some(SAMPLE_ID),
+                Some(CRUISE_ID),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            df.get_column_names()
+                .into_iter()
+                .filter(|name| {
+                    !used.contains(&name.as_str()) && !sources.iter().any(|s| s == name.as_str())
+                })
+                .map(|name| name.to_string())
+                .collect()
+        }
+    };
+    let provenance = provenance(
+        df,
+        options.sample_id_column.as_deref(),
+        options.cruise_id_column.as_deref(),
+        &metadata,
+    )?;
+    Ok(InfluenceData {
+        model: InfluenceFunction::new(weights, relative_error),
+        observations: ObservationSet::new(observations).with_provenance(provenance),
+        sources,
+    })
+}
+
+/// Names of the `influence_function_N` columns, ordered by `N`.
+fn influence_columns(df: &DataFrame) -> Result<Vec<String>, DataError> {
+    let mut numbered = df
+        .get_column_names()
+        .into_iter()
+        .filter_map(|name| {
+            let number = name.strip_prefix(INFLUENCE_PREFIX)?;
+            Some(
+                number
+                    .parse::<usize>()
+                    .map(|n| (n, name.to_string()))
+                    .map_err(|_| DataError::InvalidColumn {
+                        column: name.to_string(),
+                        reason: "is not numbered like influence_function_1".to_string(),
+                    }),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbered.is_empty() {
+        return Err(DataError::MissingColumn(format!("{}1", INFLUENCE_PREFIX)));
+    }
+    numbered.sort();
+    Ok(numbered.into_iter().map(|(_, name)| name).collect())
+}
+
+fn read_csv(path: &Path) -> Result<DataFrame, DataError> {
+    Ok(CsvReadOptions::default()
+        .with_infer_schema_length(None)
+        .with_has_header(true)
+        .try_into_reader_with_file_path(Some(path.to_path_buf()))?
+        .finish()?)
+}
+
+fn column<'a>(df: &'a DataFrame, name: &str) -> Result<&'a Series, DataError> {
+    df.column(name)
+        .map_err(|_| DataError::MissingColumn(name.to_string()))
+}
+
+fn invalid_cell(column: &str, row: usize, reason: &str) -> DataError {
+    DataError::InvalidCell {
+        column: column.to_string(),
+        row: row + 1,
+        reason: reason.to_string(),
+    }
+}
+
+fn empty(column: &str, row: usize) -> DataError {
+    invalid_cell(column, row, "is empty")
+}
+
+/// Numeric cells, with `None` for empty ones.
+fn float_cells(df: &DataFrame, name: &str) -> Result<Vec<Option<f64>>, DataError> {
+    let series =
+        column(df, name)?
+            .cast(&DataType::Float64)
+            .map_err(|_| DataError::InvalidColumn {
+                column: name.to_string(),
+                reason: "is not numeric".to_string(),
+            })?;
+    Ok(series.f64()?.into_iter().collect())
+}
+
+fn float_column(df: &DataFrame, name: &str) -> Result<Vec<f64>, DataError> {
+    float_cells(df, name)?
+        .into_iter()
+        .enumerate()
+        .map(|(row, x)| x.ok_or_else(|| empty(name, row)))
+        .collect()
+}
+
+/// Cells as text, with `None` for empty ones.
+fn string_cells(df: &DataFrame, name: &str) -> Result<Vec<Option<String>>, DataError> {
+    let series = column(df, name)?.cast(&DataType::String)?;
+    Ok(series
+        .str()?
+        .into_iter()
+        .map(|x| x.map(String::from))
+        .collect())
+}
+
+/// One `Provenance` per row from the given identifier and metadata columns.
+/// Empty metadata cells are left out.
+fn provenance(
+    df: &DataFrame,
+    sample_id: Option<&str>,
+    cruise_id: Option<&str>,
+    metadata: &[String],
+) -> Result<Vec<Provenance>, DataError> {
+    let mut rows = vec![Provenance::default(); df.height()];
+    if let Some(name) = sample_id {
+        for (row, id) in rows.iter_mut().zip(string_cells(df, name)?) {
+            row.sample_id = id;
+        }
+    }
+    if let Some(name) = cruise_id {
+        for (row, id) in rows.iter_mut().zip(string_cells(df, name)?) {
+            row.cruise_id = id;
+        }
+    }
+    for name in metadata {
+        for (row, value) in rows.iter_mut().zip(string_cells(df, name)?) {
+            if let Some(value) = value {
+                row.metadata.insert(name.clone(), value);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Booleans, or 0/1, or text such as TRUE/false.
+fn bool_column(df: &DataFrame, name: &str) -> Result<Vec<bool>, DataError> {
+    let series = column(df, name)?;
+    let invalid = |row: usize| invalid_cell(name, row, "is not a true/false value");
+    match series.dtype() {
+        DataType::Boolean => series
+            .bool()?
+            .into_iter()
+            .enumerate()
+            .map(|(row, x)| x.ok_or_else(|| invalid(row)))
+            .collect(),
+        DataType::String => series
+            .str()?
+            .into_iter()
+            .enumerate()
+            .map(
+                |(row, x)| match x.map(|s| s.trim().to_lowercase()).as_deref() {
+                    Some("true") | Some("1") => Ok(true),
+                    Some("false") | Some("0") => Ok(false),
+                    _ => Err(invalid(row)),
+                },
+            )
+            .collect(),
+        _ => float_column(df, name)?
+            .into_iter()
+            .enumerate()
+            .map(|(row, x)| {
+                if x == 1.0 {
+                    Ok(true)
+                } else if x == 0.0 {
+                    Ok(false)
+                } else {
+                    Err(invalid(row))
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::likelihood::Likelihood;
+    use crate::models::Model;
+    use emcee::Guess;
+
+    fn write_csv(name: &str, contents: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("letsbayes-{}-{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    const HEADER: &str = "sample_id,cruise_id,is_nondetect?,observed concentration,\
+                          detection limit,observation uncertainty";
+
+    #[test]
+    fn influence_csv() {
+        let data =
+            read_influence_csv("examples/ocean_influence_function/inf_inputs.csv", 0.0).unwrap();
+        assert_eq!(
+            data.sources,
+            ["influence_function_1", "influence_function_2"]
+        );
+
+        // columns are ordered by number and rows keep their order
+        let path = write_csv(
+            "influence",
+            &format!(
+                "{},influence_function_10,influence_function_2,influence_function_1,station\n\
+                 A7,1,FALSE,20.0,5.0,1.0,0.0,1.0,2.0,north\n\
+                 B2,1,TRUE,,5.0,0.5,1.0,0.0,0.0,\n",
+                HEADER
+            ),
+        );
+        let data = read_influence_csv(&path, 0.0).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            data.sources,
+            [
+                "influence_function_1",
+                "influence_function_2",
+                "influence_function_10"
+            ]
+        );
+        let proposal = Guess::new(&[4.0, 3.0, 1.0]);
+        let prediction = data.model.predict(&proposal);
+        assert_eq!(prediction.observables, vec![11.0, 1.0]);
+        let expected = Observation::new(20.0, 1.0).loglikelihood(&11.0, &0.0, &0.0)
+            + NondetectObservation::new(5.0, 0.5).loglikelihood(&1.0, &0.0, &0.0);
+        assert_eq!(data.observations.loglikelihood(prediction), expected);
+
+        let first = data.observations.provenance(0);
+        assert_eq!(first.sample_id.as_deref(), Some("A7"));
+        assert_eq!(first.cruise_id.as_deref(), Some("1"));
+        assert_eq!(first.metadata["station"], "north");
+        assert!(data.observations.provenance(1).metadata.is_empty());
+    }
+
+    #[test]
+    fn influence_csv_options() {
+        let path = write_csv(
+            "options",
+            "id,value,sd,nd,limit,source\n\
+             A,20.0,1.0,0,5.0,2.0\n\
+             B,,0.5,1,5.0,1.0\n",
+        );
+        let options = InfluenceCsvOptions {
+            value_column: "value".to_string(),
+            uncertainty_column: "sd".to_string(),
+            nondetect_column: Some("nd".to_string()),
+            detection_limit_column: Some("limit".to_string()),
+            sample_id_column: Some("id".to_string()),
+            cruise_id_column: None,
+            metadata_columns: Some(vec![]),
+            influence_columns: Some(vec!["source".to_string()]),
+            uncertainty: UncertaintyKind::Absolute,
+            detects: DetectLikelihood::Cauchy,
+            nondetects: NondetectLikelihood::LeftCensored,
+        };
+        let data = read_influence_csv_with(&path, 0.0, &options).unwrap();
+        let prediction = data.model.predict(&Guess::new(&[4.0]));
+        let expected = CauchyObservation::new(20.0, 1.0).loglikelihood(&8.0, &0.0, &0.0)
+            + LeftCensoredObservation::new(5.0, 0.5).loglikelihood(&4.0, &0.0, &0.0);
+        assert_eq!(data.observations.loglikelihood(prediction), expected);
+        assert_eq!(
+            data.observations.provenance(1).sample_id.as_deref(),
+            Some("B")
+        );
+
+        // relative errors scale with the value or detection limit
+        let relative = InfluenceCsvOptions {
+            uncertainty: UncertaintyKind::Relative,
+            ..options.clone()
+        };
+        let data = read_influence_csv_with(&path, 0.0, &relative).unwrap();
+        let prediction = data.model.predict(&Guess::new(&[4.0]));
+        let expected = CauchyObservation::new(20.0, 20.0).loglikelihood(&8.0, &0.0, &0.0)
+            + LeftCensoredObservation::new(5.0, 2.5).loglikelihood(&4.0, &0.0, &0.0);
+        assert_eq!(data.observations.loglikelihood(prediction), expected);
+        let zero = write_csv(
+            "options-zero",
+            "id,value,sd,nd,limit,source\nA,0.0,0.1,0,5.0,2.0\n",
+        );
+        let result = read_influence_csv_with(&zero, 0.0, &relative);
+        std::fs::remove_file(&zero).unwrap();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "column 'sd' is relative to a zero value in row 1"
+        );
+
+        let without_limit = InfluenceCsvOptions {
+            detection_limit_column: None,
+            ..options.clone()
+        };
+        let result = read_influence_csv_with(&path, 0.0, &without_limit);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "column 'nd' is a nondetect without a detection limit column in row 2"
+        );
+        let missing = InfluenceCsvOptions {
+            cruise_id_column: Some("cruise".to_string()),
+            ..options
+        };
+        let result = read_influence_csv_with(&path, 0.0, &missing);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "input data has no column 'cruise'"
+        );
+    }
+
+    #[test]
+    fn invalid_influence_csv() {
+        let cases = [
+            (
+                format!("{},influence_function_1\n1,1,FALSE,20.0,5.0,,1.0\n", HEADER),
+                "column 'observation uncertainty' is empty in row 1",
+            ),
+            (
+                format!(
+                    "{},influence_function_1\n1,1,FALSE,20.0,5.0,1.0,1.0\n2,1,TRUE,1.0,,1.0,1.0\n",
+                    HEADER
+                ),
+                "column 'detection limit' is empty in row 2",
+            ),
+            (
+                format!(
+                    "{},influence_function_1\n1,1,maybe,20.0,5.0,1.0,1.0\n",
+                    HEADER
+                ),
+                "column 'is_nondetect?' is not a true/false value in row 1",
+            ),
+            (
+                format!(
+                    "{},influence_function_1\n1,1,FALSE,20.0,5.0,0.0,1.0\n",
+                    HEADER
+                ),
+                "column 'observation uncertainty' must be positive in row 1",
+            ),
+            (
+                format!(
+                    "{},influence_function_a\n1,1,FALSE,20.0,5.0,1.0,1.0\n",
+                    HEADER
+                ),
+                "column 'influence_function_a' is not numbered like influence_function_1",
+            ),
+            (
+                format!("{}\n1,1,FALSE,20.0,5.0,1.0\n", HEADER),
+                "input data has no column 'influence_function_1'",
+            ),
+        ];
+        for (i, (contents, message)) in cases.iter().enumerate() {
+            let path = write_csv(&format!("invalid-{}", i), contents);
+            let result = read_influence_csv(&path, 0.0);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(result.err().unwrap().to_string(), *message);
+        }
+    }
+}
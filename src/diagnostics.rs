@@ -0,0 +1,201 @@
+use std::collections::BTreeSet;
+
+use crate::likelihood::Provenance;
+
+/// Reasons per-observation diagnostics cannot be computed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagnosticsError {
+    ZeroThinning,
+    /// Nothing is left after skipping and thinning the posterior.
+    NoSamples,
+}
+
+impl std::fmt::Display for DiagnosticsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticsError::ZeroThinning => write!(f, "thinning must be at least 1"),
+            DiagnosticsError::NoSamples => {
+                write!(
+                    f,
+                    "no posterior samples are left after burn-in and thinning"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticsError {}
+
+/// Posterior fit of a single observation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservationDiagnostics {
+    pub provenance: Provenance,
+    /// Reported value, or the limit of a censored observation.
+    pub observed: Option<f64>,
+    /// Whether `observed` is a censoring limit.
+    pub censored: bool,
+    /// Posterior mean of the model prediction.
+    pub predicted_mean: f64,
+    /// 5th and 95th percentiles of the posterior predictive distribution of
+    /// the measured value, i.e. the prediction plus measurement noise.
+    pub predictive_lower: f64,
+    pub predictive_upper: f64,
+    /// Log-likelihood of this observation averaged over posterior samples.
+    pub mean_loglikelihood: f64,
+    /// Posterior probability that the observation is an outlier, for
+    /// likelihoods that model outliers.
+    pub outlier_probability: Option<f64>,
+}
+
+impl ObservationDiagnostics {
+    pub(crate) fn new(
+        provenance: Provenance,
+        observed: Option<f64>,
+        censored: bool,
+        predictions: Vec<f64>,
+        mut draws: Vec<f64>,
+        mean_loglikelihood: f64,
+        outlier_probability: Option<f64>,
+    ) -> Self {
+        assert!(
+            !predictions.is_empty() && !draws.is_empty(),
+            "need at least one posterior sample"
+        );
+        draws.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f64| draws[((draws.len() - 1) as f64 * p).round() as usize];
+        Self {
+            provenance,
+            observed,
+            censored,
+            predicted_mean: predictions.iter().sum::<f64>() / predictions.len() as f64,
+            predictive_lower: percentile(0.05),
+            predictive_upper: percentile(0.95),
+            mean_loglikelihood,
+            outlier_probability,
+        }
+    }
+
+    /// Observed minus mean predicted value. `None` for censored
+    /// observations, whose true value is unknown.
+    pub fn residual(&self) -> Option<f64> {
+        if self.censored {
+            None
+        } else {
+            self.observed.map(|o| o - self.predicted_mean)
+        }
+    }
+}
+
+/// Write one row per observation, led by its sample and cruise IDs and
+/// metadata columns.
+pub fn to_csv(diagnostics: &[ObservationDiagnostics], filename: &str) -> std::io::Result<()> {
+    let metadata: BTreeSet<&String> = diagnostics
+        .iter()
+        .flat_map(|d| d.provenance.metadata.keys())
+        .collect();
+    let optional = |x: Option<f64>| x.map(|v| v.to_string()).unwrap_or_default();
+
+    let mut header = vec!["sample_id".to_string(), "cruise_id".to_string()];
+    header.extend(metadata.iter().map(|k| escape(k)));
+    header.extend(
+        [
+            "observed",
+            "predicted_mean",
+            "predictive_lower",
+            "predictive_upper",
+            "residual",
+            "loglikelihood",
+            "outlier_probability",
+        ]
+        .map(String::from),
+    );
+    let mut string_out = header.join(",");
+    string_out.push('\n');
+    for d in diagnostics {
+        let p = &d.provenance;
+        let mut row = vec![
+            escape(p.sample_id.as_deref().unwrap_or_default()),
+            escape(p.cruise_id.as_deref().unwrap_or_default()),
+        ];
+        row.extend(
+            metadata
+                .iter()
+                .map(|k| escape(p.metadata.get(*k).map_or("", |v| v))),
+        );
+        row.extend([
+            optional(d.observed),
+            d.predicted_mean.to_string(),
+            d.predictive_lower.to_string(),
+            d.predictive_upper.to_string(),
+            optional(d.residual()),
+            d.mean_loglikelihood.to_string(),
+            optional(d.outlier_probability),
+        ]);
+        string_out.push_str(&row.join(","));
+        string_out.push('\n');
+    }
+    std::fs::write(filename, string_out)
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_and_csv() {
+        let mut provenance = Provenance {
+            sample_id: Some("S1".to_string()),
+            cruise_id: Some("C1".to_string()),
+            ..Default::default()
+        };
+        provenance
+            .metadata
+            .insert("station".to_string(), "North, 2".to_string());
+        let predictions = vec![49.0, 51.0];
+        let draws: Vec<f64> = (0..=100).rev().map(|x| x as f64).collect();
+        let d = ObservationDiagnostics::new(
+            provenance,
+            Some(60.0),
+            false,
+            predictions,
+            draws,
+            -1.5,
+            Some(0.25),
+        );
+        assert_eq!(d.predicted_mean, 50.0);
+        assert_eq!((d.predictive_lower, d.predictive_upper), (5.0, 95.0));
+        assert_eq!(d.residual(), Some(10.0));
+
+        // no residual against a detection limit
+        let nondetect = ObservationDiagnostics::new(
+            Provenance::default(),
+            Some(5.0),
+            true,
+            vec![1.0],
+            vec![2.0],
+            -0.5,
+            None,
+        );
+        assert_eq!(nondetect.residual(), None);
+        let path = std::env::temp_dir().join(format!("letsbayes-diag-{}.csv", std::process::id()));
+        to_csv(&[d, nondetect], path.to_str().unwrap()).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written,
+            "sample_id,cruise_id,station,observed,predicted_mean,predictive_lower,\
+             predictive_upper,residual,loglikelihood,outlier_probability\n\
+             S1,C1,\"North, 2\",60,50,5,95,10,-1.5,0.25\n\
+             ,,,5,1,2,2,,-0.5,\n"
+        );
+    }
+}
@@ -1,23 +1,67 @@
+pub mod config;
+pub mod data;
+pub mod diagnostics;
 pub mod likelihood;
+mod linalg;
 pub mod models;
 pub mod posterior;
 pub mod priors;
+pub mod transforms;
 
-use likelihood::Likelihood;
+use diagnostics::{DiagnosticsError, ObservationDiagnostics};
+use likelihood::{Likelihood, ObservationSet};
 use models::Model;
 use posterior::Posterior;
-use priors::Prior;
+use priors::{CategoricalPrior, Prior, PriorError};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
+use transforms::Transform;
 
 use emcee::{EnsembleSampler, Guess, Prob};
 
 #[derive(Serialize, Deserialize)]
+#[serde(try_from = "InferenceProblemRaw<P, L, M>")]
 pub struct InferenceProblem<P: Prior, L: Likelihood, M: Model> {
     pub prior: P,
     pub likelihood: L,
     pub model: M,
     parameter_names: Vec<String>,
     dimension: usize,
+    transform: Option<Box<dyn Transform>>,
+    category: Option<CategoricalPrior>,
+}
+
+/// Unchecked `InferenceProblem` as read from JSON.
+#[derive(Deserialize)]
+struct InferenceProblemRaw<P: Prior, L: Likelihood, M: Model> {
+    prior: P,
+    likelihood: L,
+    model: M,
+    parameter_names: Vec<String>,
+    transform: Option<Box<dyn Transform>>,
+    category: Option<CategoricalPrior>,
+}
+
+impl<P: Prior, L: Likelihood, M: Model> TryFrom<InferenceProblemRaw<P, L, M>>
+    for InferenceProblem<P, L, M>
+{
+    type Error = PriorError;
+
+    fn try_from(raw: InferenceProblemRaw<P, L, M>) -> Result<Self, PriorError> {
+        let mut problem = Self {
+            prior: raw.prior,
+            likelihood: raw.likelihood,
+            model: raw.model,
+            parameter_names: raw.parameter_names,
+            dimension: 0,
+            transform: raw.transform,
+            category: raw.category,
+        };
+        problem.check()?;
+        problem.update_dimension();
+        Ok(problem)
+    }
 }
 
 impl<P: Prior, L: Likelihood, M: Model> InferenceProblem<P, L, M> {
@@ -29,69 +73,427 @@ impl<P: Prior, L: Likelihood, M: Model> InferenceProblem<P, L, M> {
             model,
             parameter_names,
             dimension,
+            transform: None,
+            category: None,
         }
     }
 
-    pub fn new_unnamed(prior: P, likelihood: L, model: M, dimension: usize) -> Self {
-        let parameter_names = (0..dimension)
+    /// Like `new`, but fails if the prior does not cover exactly one value
+    /// per named parameter, the likelihood samples a hyperparameter that is
+    /// not one of them, or its error-inflation groups are undeclared or
+    /// misnamed.
+    pub fn try_new(
+        prior: P,
+        likelihood: L,
+        model: M,
+        parameter_names: Vec<String>,
+    ) -> Result<Self, PriorError> {
+        let problem = Self::new(prior, likelihood, model, parameter_names);
+        problem.check()?;
+        Ok(problem)
+    }
+
+    /// The checks of `try_new` and `try_with_transform`, counting only the
+    /// continuous parameters when a category is marginalized.
+    fn check(&self) -> Result<(), PriorError> {
+        self.check_transform()?;
+        let prior_dimension = self.prior.initial_guess().values.len();
+        if prior_dimension != self.n_continuous() {
+            return Err(PriorError::DimensionMismatch {
+                prior: prior_dimension,
+                parameters: self.n_continuous(),
+            });
+        }
+        let n_parameters = self.parameter_names.len();
+        if let Some(index) = self
+            .likelihood
+            .inferred_parameters()
             .into_iter()
-            .map(|x| format!("p{}", x))
-            .collect();
+            .find(|i| *i >= n_parameters)
+        {
+            return Err(PriorError::HyperparameterIndex {
+                index,
+                parameters: n_parameters,
+            });
+        }
+        self.likelihood.check_error_groups(&self.parameter_names)
+    }
+
+    pub fn new_unnamed(prior: P, likelihood: L, model: M, dimension: usize) -> Self {
+        let parameter_names = (0..dimension).map(|x| format!("p{}", x)).collect();
         Self {
             prior,
             likelihood,
             model,
             parameter_names,
             dimension,
+            transform: None,
+            category: None,
+        }
+    }
+
+    /// Sample on the unconstrained side of `transform`. The prior, model and
+    /// posterior all see the transformed (constrained) parameters, and the
+    /// Jacobian is added to the prior.
+    pub fn with_transform(self, transform: impl Transform + 'static) -> Self {
+        self.try_with_transform(transform)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `with_transform`, but fails unless the transform produces one
+    /// value per continuous parameter.
+    pub fn try_with_transform(
+        mut self,
+        transform: impl Transform + 'static,
+    ) -> Result<Self, PriorError> {
+        self.transform = Some(Box::new(transform));
+        self.check_transform()?;
+        self.update_dimension();
+        Ok(self)
+    }
+
+    /// Add a discrete category parameter called `name` after the named
+    /// parameters, with the given prior. The sampler only moves the
+    /// continuous parameters: the likelihood is summed over categories, and
+    /// each posterior sample gets a category drawn from its conditional
+    /// posterior (a Gibbs-style step). The model receives the category index
+    /// as the last proposal value; the prior and any transform cover the
+    /// continuous parameters only, so they are built and checked against the
+    /// names given to `new` or `try_new`.
+    pub fn with_marginalized_category(mut self, name: &str, category: CategoricalPrior) -> Self {
+        self.parameter_names.push(name.to_string());
+        self.category = Some(category);
+        self.update_dimension();
+        self
+    }
+
+    fn n_continuous(&self) -> usize {
+        self.parameter_names.len() - self.category.is_some() as usize
+    }
+
+    fn check_transform(&self) -> Result<(), PriorError> {
+        match &self.transform {
+            Some(t) if t.constrained_dimension() != self.n_continuous() => {
+                Err(PriorError::TransformDimension {
+                    transform: t.constrained_dimension(),
+                    parameters: self.n_continuous(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update_dimension(&mut self) {
+        self.dimension = match &self.transform {
+            Some(t) => t.unconstrained_dimension(),
+            None => self.n_continuous(),
+        };
+    }
+
+    fn with_category(params: &Guess, category: usize) -> Guess {
+        let mut values = params.values.clone();
+        values.push(category as f32);
+        Guess::new(&values)
+    }
+
+    /// Log-likelihood for constrained continuous parameters and, when a
+    /// category is marginalized, each category's prior-weighted term.
+    fn category_loglikelihoods(&self, params: &Guess) -> Vec<f64> {
+        match &self.category {
+            Some(c) => (0..c.n_categories())
+                .map(|k| {
+                    let full = Self::with_category(params, k);
+                    let prediction = self.model.predict(&full);
+                    c.logprobability(k)
+                        + self
+                            .likelihood
+                            .loglikelihood_with_proposal(prediction, &full)
+                })
+                .collect(),
+            None => {
+                let prediction = self.model.predict(params);
+                vec![self
+                    .likelihood
+                    .loglikelihood_with_proposal(prediction, params)]
+            }
+        }
+    }
+
+    /// Map sampler output to reported samples: constrain, then draw the
+    /// category from its conditional posterior (or prior, without the
+    /// likelihood).
+    fn complete_samples(
+        &self,
+        chain: Vec<Guess>,
+        use_likelihood: bool,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Guess> {
+        chain
+            .iter()
+            .map(|x| {
+                let params = self.constrain(x);
+                match &self.category {
+                    Some(c) => {
+                        let weights: Vec<f64> = if use_likelihood {
+                            self.category_loglikelihoods(&params)
+                        } else {
+                            (0..c.n_categories()).map(|k| c.logprobability(k)).collect()
+                        };
+                        Self::with_category(&params, CategoricalPrior::draw(&weights, rng))
+                    }
+                    None => params,
+                }
+            })
+            .collect()
+    }
+
+    /// Map a sampler proposal to model parameters.
+    pub fn constrain(&self, params: &Guess) -> Guess {
+        match &self.transform {
+            Some(t) => Guess::new(&to_f32(&t.forward(&to_f64(&params.values)))),
+            None => params.clone(),
         }
     }
 
     pub fn generate_initial(&self, walkers_per_dim: usize) -> Vec<Guess> {
-        self.prior
-            .initial_guess()
-            .create_initial_guess(self.dimension * walkers_per_dim)
+        self.generate_initial_with_rng(walkers_per_dim, &mut rand::thread_rng())
+    }
+
+    /// Initial walker positions drawn from the prior with the given generator.
+    pub fn generate_initial_with_rng(
+        &self,
+        walkers_per_dim: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Guess> {
+        let ensemble = self
+            .prior
+            .initial_ensemble(self.dimension * walkers_per_dim, rng);
+        match &self.transform {
+            Some(t) => ensemble
+                .iter()
+                .map(|g| Guess::new(&to_f32(&t.inverse(&to_f64(&g.values)))))
+                .collect(),
+            None => ensemble,
+        }
+    }
+}
+
+fn to_f64(values: &[f32]) -> Vec<f64> {
+    values.iter().map(|x| *x as f64).collect()
+}
+
+fn to_f32(values: &[f64]) -> Vec<f32> {
+    values.iter().map(|x| *x as f32).collect()
+}
+
+/// The prior alone, including any transform Jacobian, as an emcee target.
+struct PriorProblem<'a, P: Prior, L: Likelihood, M: Model>(&'a InferenceProblem<P, L, M>);
+
+impl<P: Prior, L: Likelihood, M: Model> Prob for PriorProblem<'_, P, L, M> {
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.0.lnprior(params)
     }
 }
 
 impl<P: Prior, L: Likelihood, M: Model> Prob for InferenceProblem<P, L, M> {
     fn lnlike(&self, params: &Guess) -> f32 {
-        let prediction = self.model.predict(params);
-        self.likelihood.loglikelihood(prediction) as f32
+        let terms = self.category_loglikelihoods(&self.constrain(params));
+        let max = terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY {
+            return f32::NEG_INFINITY;
+        }
+        (max + terms.iter().map(|t| (t - max).exp()).sum::<f64>().ln()) as f32
     }
     fn lnprior(&self, params: &Guess) -> f32 {
-        self.prior.logprobability(params) as f32
+        match &self.transform {
+            Some(t) => {
+                let lp = self.prior.logprobability(&self.constrain(params));
+                (lp + t.log_jacobian(&to_f64(&params.values))) as f32
+            }
+            None => self.prior.logprobability(params) as f32,
+        }
     }
 }
 
 impl<P: Prior + Prob, L: Likelihood, M: Model> InferenceProblem<P, L, M> {
     pub fn sample(&self, n_iterations: usize, walkers_per_dim: usize) -> Posterior {
-        let ndim = self.dimension;
-        let nwalkers = ndim * walkers_per_dim;
-        let mut sampler =
-            EnsembleSampler::new(nwalkers, ndim, self).expect("could not create sampler");
-        let perturbed_guess = self.generate_initial(walkers_per_dim);
-        sampler
-            .run_mcmc(&perturbed_guess, n_iterations)
-            .expect("error running sampler");
+        self.run_sampler(self, n_iterations, walkers_per_dim, None, true)
+    }
 
-        Posterior::new(self.parameter_names.clone(), sampler.flatchain())
+    /// Like `sample`, but reproducible: the same seed gives the same
+    /// posterior samples.
+    pub fn sample_seeded(
+        &self,
+        n_iterations: usize,
+        walkers_per_dim: usize,
+        seed: u64,
+    ) -> Posterior {
+        self.run_sampler(self, n_iterations, walkers_per_dim, Some(seed), true)
     }
 
     pub fn sample_prior(&self, n_iterations: usize, walkers_per_dim: usize) -> Posterior {
+        let prior = PriorProblem(self);
+        self.run_sampler(&prior, n_iterations, walkers_per_dim, None, false)
+    }
+
+    fn run_sampler<T: Prob>(
+        &self,
+        target: &T,
+        n_iterations: usize,
+        walkers_per_dim: usize,
+        seed: Option<u64>,
+        use_likelihood: bool,
+    ) -> Posterior {
         let ndim = self.dimension;
         let nwalkers = ndim * walkers_per_dim;
         let mut sampler =
-            EnsembleSampler::new(nwalkers, ndim, &self.prior).expect("could not create sampler");
-        let perturbed_guess = self.generate_initial(walkers_per_dim);
+            EnsembleSampler::new(nwalkers, ndim, target).expect("could not create sampler");
+        let mut rng: Box<dyn RngCore> = match seed {
+            Some(seed) => {
+                sampler.seed(&[seed as usize]);
+                Box::new(StdRng::seed_from_u64(seed))
+            }
+            None => Box::new(rand::thread_rng()),
+        };
+        let perturbed_guess = self.generate_initial_with_rng(walkers_per_dim, &mut rng);
         sampler
             .run_mcmc(&perturbed_guess, n_iterations)
             .expect("error running sampler");
 
-        Posterior::new(self.parameter_names.clone(), sampler.flatchain())
+        Posterior::new(
+            self.parameter_names.clone(),
+            self.complete_samples(sampler.flatchain(), use_likelihood, &mut rng),
+        )
+    }
+}
+
+impl<P: Prior, M: Model> InferenceProblem<P, ObservationSet, M> {
+    /// Posterior probability that each observation is an outlier, averaged
+    /// over the samples kept after discarding `skip` and keeping every
+    /// `thinning`-th. Samples where the probability is undefined are left
+    /// out of the average. `None` for observations whose likelihood does not
+    /// model outliers. Fails if `thinning` is zero or no samples are kept.
+    pub fn outlier_probabilities(
+        &self,
+        posterior: &Posterior,
+        skip: usize,
+        thinning: usize,
+    ) -> Result<Vec<Option<f64>>, DiagnosticsError> {
+        let mut totals = vec![(0.0, 0); self.likelihood.observations.len()];
+        for sample in diagnostic_samples(posterior, skip, thinning)?.iter() {
+            let prediction = self.model.predict(sample);
+            let probabilities = self.likelihood.outlier_probabilities(prediction, sample);
+            for ((sum, count), p) in totals.iter_mut().zip(probabilities) {
+                if let Some(p) = p {
+                    *sum += p;
+                    *count += 1;
+                }
+            }
+        }
+        Ok(totals
+            .into_iter()
+            .map(|(sum, count)| (count > 0).then(|| sum / count as f64))
+            .collect())
+    }
+
+    /// Posterior fit of each observation, labelled with its provenance, over
+    /// the samples kept after discarding `skip` and keeping every
+    /// `thinning`-th, as in `Posterior::to_csv`. The predictive interval
+    /// draws measurement noise for each sample from the observation's
+    /// likelihood. Fails if `thinning` is zero or no samples are kept.
+    pub fn observation_diagnostics(
+        &self,
+        posterior: &Posterior,
+        skip: usize,
+        thinning: usize,
+    ) -> Result<Vec<ObservationDiagnostics>, DiagnosticsError> {
+        self.run_diagnostics(posterior, skip, thinning, &mut rand::thread_rng())
+    }
+
+    /// Like `observation_diagnostics`, but reproducible: the same seed gives
+    /// the same predictive intervals.
+    pub fn observation_diagnostics_seeded(
+        &self,
+        posterior: &Posterior,
+        skip: usize,
+        thinning: usize,
+        seed: u64,
+    ) -> Result<Vec<ObservationDiagnostics>, DiagnosticsError> {
+        self.run_diagnostics(posterior, skip, thinning, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn run_diagnostics(
+        &self,
+        posterior: &Posterior,
+        skip: usize,
+        thinning: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<ObservationDiagnostics>, DiagnosticsError> {
+        let samples = diagnostic_samples(posterior, skip, thinning)?;
+        let outlier_probabilities = self.outlier_probabilities(posterior, skip, thinning)?;
+        let n_observations = self.likelihood.observations.len();
+        let mut predictions = vec![Vec::with_capacity(samples.len()); n_observations];
+        let mut draws = vec![Vec::with_capacity(samples.len()); n_observations];
+        let mut loglikelihoods = vec![0.0; n_observations];
+        for sample in samples.iter() {
+            let prediction = self.model.predict(sample);
+            for (p, value) in predictions.iter_mut().zip(&prediction.observables) {
+                p.push(*value);
+            }
+            let measured = self.likelihood.predictive_samples(&prediction, sample, rng);
+            for (d, value) in draws.iter_mut().zip(measured) {
+                d.push(value);
+            }
+            let terms = self
+                .likelihood
+                .observation_loglikelihoods(prediction, sample);
+            for (total, ll) in loglikelihoods.iter_mut().zip(terms) {
+                *total += ll / samples.len() as f64;
+            }
+        }
+        Ok(predictions
+            .into_iter()
+            .zip(draws)
+            .zip(loglikelihoods)
+            .zip(outlier_probabilities)
+            .enumerate()
+            .map(|(i, (((p, d), ll), outlier))| {
+                let observation = &self.likelihood.observations[i];
+                ObservationDiagnostics::new(
+                    self.likelihood.provenance(i),
+                    observation.reported_value(),
+                    observation.is_censored(),
+                    p,
+                    d,
+                    ll,
+                    outlier,
+                )
+            })
+            .collect())
     }
 }
 
+/// The thinned posterior samples, which must not be empty.
+fn diagnostic_samples(
+    posterior: &Posterior,
+    skip: usize,
+    thinning: usize,
+) -> Result<Vec<Guess>, DiagnosticsError> {
+    if thinning == 0 {
+        return Err(DiagnosticsError::ZeroThinning);
+    }
+    let samples = posterior.thinned_samples(skip, thinning);
+    if samples.is_empty() {
+        return Err(DiagnosticsError::NoSamples);
+    }
+    Ok(samples)
+}
+
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants, clippy::let_and_return)]
 mod tests {
     use statrs::assert_almost_eq;
 
@@ -105,6 +507,378 @@ mod tests {
     #[test]
     fn square() {}
 
+    #[test]
+    fn outlier_probabilities() {
+        use likelihood::{Observation, OutlierMixtureObservation};
+        use models::Prediction;
+        use priors::BasicPrior;
+
+        // every observation is predicted by the first parameter
+        struct Constant;
+        impl Model for Constant {
+            fn predict(&self, proposal: &Guess) -> Prediction {
+                Prediction::new(vec![proposal.values[0] as f64; 3], vec![0.0; 3], 0.0)
+            }
+        }
+        let model = Constant;
+        let obs = ObservationSet::new(vec![
+            Box::new(Observation::new(1.0, 0.1)),
+            Box::new(OutlierMixtureObservation::new_inferred(1.0, 0.1, 10.0, 1)),
+            Box::new(OutlierMixtureObservation::new_inferred(5.0, 0.1, 10.0, 1)),
+        ]);
+        let problem = InferenceProblem::new(
+            BasicPrior::new(vec![]),
+            obs,
+            model,
+            vec!["source".to_string(), "outlier_fraction".to_string()],
+        );
+        let samples = vec![
+            Guess::new(&[1.0, 0.1]),
+            Guess::new(&[1.0, 0.2]),
+            // invalid fraction, so no probability for this sample
+            Guess::new(&[1.0, 1.5]),
+        ];
+        let posterior = Posterior::new(problem.parameter_names.clone(), samples.clone());
+        let probabilities = problem.outlier_probabilities(&posterior, 0, 1).unwrap();
+        assert_eq!(probabilities[0], None);
+        assert!(probabilities[1].unwrap() < 0.1);
+        assert!(probabilities[2].unwrap() > 0.9);
+        let valid = Posterior::new(problem.parameter_names.clone(), samples[..2].to_vec());
+        assert_eq!(
+            probabilities,
+            problem.outlier_probabilities(&valid, 0, 1).unwrap()
+        );
+
+        // burn-in is skipped
+        let second = Posterior::new(problem.parameter_names.clone(), samples[1..2].to_vec());
+        assert_eq!(
+            problem.outlier_probabilities(&posterior, 1, 2),
+            problem.outlier_probabilities(&second, 0, 1)
+        );
+        let diagnostics = problem.observation_diagnostics(&posterior, 0, 1).unwrap();
+        assert_eq!(diagnostics[0].outlier_probability, None);
+        assert_eq!(diagnostics[2].outlier_probability, probabilities[2]);
+
+        // the predictive interval includes the measurement noise
+        let fixed = vec![Guess::new(&[1.0, 0.1]); 400];
+        let fixed = Posterior::new(problem.parameter_names.clone(), fixed);
+        let diagnostics = problem
+            .observation_diagnostics_seeded(&fixed, 0, 1, 7)
+            .unwrap();
+        assert_eq!(diagnostics[0].predicted_mean, 1.0);
+        assert_almost_eq!(diagnostics[0].predictive_lower, 1.0 - 1.645 * 0.1, 0.03);
+        assert_almost_eq!(diagnostics[0].predictive_upper, 1.0 + 1.645 * 0.1, 0.03);
+        assert_eq!(
+            diagnostics,
+            problem
+                .observation_diagnostics_seeded(&fixed, 0, 1, 7)
+                .unwrap()
+        );
+
+        // too much burn-in or no thinning is an error rather than a panic
+        assert_eq!(
+            problem.observation_diagnostics(&posterior, 3, 1),
+            Err(DiagnosticsError::NoSamples)
+        );
+        assert_eq!(
+            problem.outlier_probabilities(&posterior, 0, 0),
+            Err(DiagnosticsError::ZeroThinning)
+        );
+    }
+
+    #[test]
+    fn dirichlet_apportionment() {
+        use likelihood::Observation;
+        use models::Prediction;
+        use priors::DirichletPrior;
+        use transforms::SimplexTransform;
+
+        // observations are the fractions themselves
+        struct Fractions;
+        impl Model for Fractions {
+            fn predict(&self, proposal: &Guess) -> Prediction {
+                let values = proposal.values.iter().map(|x| *x as f64).collect();
+                Prediction::new(values, vec![0.0; 3], 0.0)
+            }
+        }
+        let obs = ObservationSet::new(vec![
+            Box::new(Observation::new(0.2, 0.05)),
+            Box::new(Observation::new(0.3, 0.05)),
+            Box::new(Observation::new(0.5, 0.05)),
+        ]);
+        let problem = InferenceProblem::new(
+            DirichletPrior::new(vec![1.0, 1.0, 1.0]),
+            obs,
+            Fractions,
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        )
+        .with_transform(SimplexTransform::new(3));
+        assert_eq!(problem.generate_initial(4)[0].values.len(), 2);
+
+        let posterior = problem.sample_seeded(500, 8, 42);
+        assert_eq!(posterior.parameter_names(), &["a", "b", "c"]);
+        let samples = posterior.samples();
+        for s in samples.iter() {
+            assert_almost_eq!(s.values.iter().sum::<f32>() as f64, 1.0, 1e-4);
+        }
+        let mean_c = samples.iter().map(|s| s.values[2]).sum::<f32>() / samples.len() as f32;
+        assert_almost_eq!(mean_c as f64, 0.5, 0.1);
+    }
+
+    #[test]
+    fn marginalized_category() {
+        use likelihood::Observation;
+        use models::Prediction;
+        use priors::{BasicPrior, IndependentPrior};
+        use statrs::distribution::Uniform;
+
+        // release on day 0 or day 1; only day 1 reaches the second sample
+        struct Release;
+        impl Model for Release {
+            fn predict(&self, proposal: &Guess) -> Prediction {
+                let strength = proposal.values[0] as f64;
+                let day = proposal.values[1] as usize;
+                let observables = if day == 1 {
+                    vec![strength, strength]
+                } else {
+                    vec![strength, 0.0]
+                };
+                Prediction::new(observables, vec![0.0; 2], 0.0)
+            }
+        }
+        let obs = ObservationSet::new(vec![
+            Box::new(Observation::new(10.0, 0.5)),
+            Box::new(Observation::new(10.0, 0.5)),
+        ]);
+        let prior = BasicPrior::new(vec![Box::new(IndependentPrior {
+            distribution: Uniform::new(0.0, 20.0).unwrap(),
+        })]);
+        let problem = InferenceProblem::new(prior, obs, Release, vec!["strength".to_string()])
+            .with_marginalized_category("day", CategoricalPrior::uniform(2));
+        assert_eq!(problem.generate_initial(8)[0].values.len(), 1);
+
+        let posterior = problem.sample_seeded(300, 16, 42);
+        let days = posterior.parameter_samples("day").unwrap();
+        let day_one = days.iter().filter(|d| **d == 1.0).count() as f64 / days.len() as f64;
+        assert!(day_one > 0.99);
+        let strength = posterior.parameter_samples("strength").unwrap();
+        let mean = strength.iter().sum::<f32>() / strength.len() as f32;
+        assert_almost_eq!(mean as f64, 10.0, 0.5);
+
+        // priors are built and checked for the continuous parameters only
+        let names = vec!["strength".to_string()];
+        let prior = priors::PriorBuilder::new()
+            .with(
+                "strength",
+                IndependentPrior {
+                    distribution: Uniform::new(0.0, 20.0).unwrap(),
+                },
+            )
+            .build(&names)
+            .unwrap();
+        let obs = ObservationSet::new(vec![Box::new(Observation::new(10.0, 0.5))]);
+        let problem = InferenceProblem::try_new(prior, obs, Release, names)
+            .unwrap()
+            .with_marginalized_category("day", CategoricalPrior::uniform(2));
+        assert_eq!(problem.parameter_names, ["strength", "day"]);
+        assert_eq!(problem.generate_initial(8)[0].values.len(), 1);
+    }
+
+    #[test]
+    fn try_new_checks_dimension() {
+        use models::InfluenceFunction;
+        use priors::{BasicPrior, IndependentPrior, PriorBuilder};
+        use statrs::distribution::Uniform;
+
+        let names = vec!["a".to_string(), "b".to_string()];
+        let uniform = || IndependentPrior {
+            distribution: Uniform::new(0.0, 1.0).unwrap(),
+        };
+        let prior = BasicPrior::new(vec![Box::new(uniform())]);
+        let model = InfluenceFunction::new(vec![vec![1.0], vec![1.0]], 0.0);
+        let result =
+            InferenceProblem::try_new(prior, ObservationSet::new(vec![]), model, names.clone());
+        assert_eq!(
+            result.err(),
+            Some(PriorError::DimensionMismatch {
+                prior: 1,
+                parameters: 2
+            })
+        );
+
+        let prior = PriorBuilder::new()
+            .with("a", uniform())
+            .with("b", uniform())
+            .build(&names)
+            .unwrap();
+        let model = InfluenceFunction::new(vec![vec![1.0], vec![1.0]], 0.0);
+        assert!(InferenceProblem::try_new(
+            prior,
+            ObservationSet::new(vec![]),
+            model,
+            names.clone()
+        )
+        .is_ok());
+
+        // hyperparameters must be sampled as one of the named parameters
+        let obs = ObservationSet::new(vec![Box::new(
+            likelihood::StudentTObservation::new_inferred(1.0, 1.0, 2),
+        )]);
+        let prior = BasicPrior::new(vec![Box::new(uniform()), Box::new(uniform())]);
+        let model = InfluenceFunction::new(vec![vec![1.0], vec![1.0]], 0.0);
+        let result = InferenceProblem::try_new(prior, obs, model, names.clone());
+        assert_eq!(
+            result.err(),
+            Some(PriorError::HyperparameterIndex {
+                index: 2,
+                parameters: 2
+            })
+        );
+
+        // error groups must be declared under the name of their parameter
+        let inflated = || {
+            ObservationSet::new(vec![Box::new(
+                likelihood::Observation::new(1.0, 1.0).inflate_error(1),
+            )])
+        };
+        let try_new = |obs: ObservationSet| {
+            let prior = BasicPrior::new(vec![Box::new(uniform()), Box::new(uniform())]);
+            let model = InfluenceFunction::new(vec![vec![1.0], vec![1.0]], 0.0);
+            InferenceProblem::try_new(prior, obs, model, names.clone()).err()
+        };
+        assert_eq!(
+            try_new(inflated()),
+            Some(PriorError::UndeclaredErrorGroup(1))
+        );
+        assert_eq!(
+            try_new(inflated().with_error_group("a", 1)),
+            Some(PriorError::ErrorGroupMismatch {
+                group: "a".to_string(),
+                index: 1
+            })
+        );
+        assert_eq!(try_new(inflated().with_error_group("b", 1)), None);
+    }
+
+    #[test]
+    fn bounded_parameter_transform() {
+        use likelihood::Observation;
+        use models::Prediction;
+        use priors::{BasicPrior, IndependentPrior};
+        use statrs::distribution::Uniform;
+        use transforms::{Bijector, ParameterTransform};
+
+        struct Identity;
+        impl Model for Identity {
+            fn predict(&self, proposal: &Guess) -> Prediction {
+                Prediction::new(vec![proposal.values[0] as f64], vec![0.0], 0.0)
+            }
+        }
+        // data pile up against the lower bound of the prior
+        let obs = ObservationSet::new(vec![Box::new(Observation::new(0.0, 0.1))]);
+        let prior = BasicPrior::new(vec![Box::new(IndependentPrior {
+            distribution: Uniform::new(0.0, 1.0).unwrap(),
+        })]);
+        let problem = InferenceProblem::new(prior, obs, Identity, vec!["x".to_string()])
+            .with_transform(ParameterTransform::new(vec![Bijector::Logit {
+                lower: 0.0,
+                upper: 1.0,
+            }]));
+        let posterior = problem.sample_seeded(500, 16, 42);
+        let x = posterior.parameter_samples("x").unwrap();
+        assert!(x.iter().all(|v| (0.0..=1.0).contains(v)));
+        // half-normal with sd 0.1 has mean 0.1 * sqrt(2 / pi)
+        let mean = x.iter().sum::<f32>() / x.len() as f32;
+        assert_almost_eq!(mean as f64, 0.08, 0.02);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        use likelihood::{LeftCensoredObservation, Observation, StudentTObservation};
+        use models::InfluenceFunction;
+        use priors::{BasicPrior, IndependentPrior, LogNormalPrior, SpikeAndSlabPrior};
+        use statrs::distribution::{Normal, Uniform};
+        use transforms::{Bijector, ParameterTransform};
+
+        let prior = BasicPrior::new(vec![
+            Box::new(
+                IndependentPrior {
+                    distribution: Normal::new(50.0, 30.0).unwrap(),
+                }
+                .truncated(0.0, f64::INFINITY),
+            ),
+            Box::new(SpikeAndSlabPrior::new(
+                0.5,
+                0.01,
+                Box::new(IndependentPrior {
+                    distribution: Uniform::new(0.0, 100.0).unwrap(),
+                }),
+            )),
+            Box::new(LogNormalPrior::new(1.0, 2.0)),
+        ]);
+        let obs = ObservationSet::new(vec![
+            Box::new(Observation::new(30.0, 3.0).inflate_error(2)),
+            Box::new(LeftCensoredObservation::new(5.0, 1.0)),
+            Box::new(StudentTObservation::new(20.0, 2.0, 4.0)),
+        ])
+        .with_error_group("inflation", 2);
+        let model = InfluenceFunction::new(
+            vec![vec![0.5, 0.1, 0.3], vec![0.2, 0.0, 0.4], vec![0.0; 3]],
+            0.1,
+        );
+        let names = vec!["a".to_string(), "b".to_string(), "inflation".to_string()];
+        let problem = InferenceProblem::new(prior, obs, model, names).with_transform(
+            ParameterTransform::new(vec![Bijector::Softplus, Bijector::Identity, Bijector::Log]),
+        );
+
+        let json = serde_json::to_string(&problem).unwrap();
+        let reloaded: InferenceProblem<BasicPrior, ObservationSet, InfluenceFunction> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&reloaded).unwrap(), json);
+        for values in [[3.5, 20.0, 0.2], [4.0, 0.001, -0.5]] {
+            let proposal = Guess::new(&values);
+            assert_eq!(reloaded.lnprior(&proposal), problem.lnprior(&proposal));
+            assert_eq!(reloaded.lnlike(&proposal), problem.lnlike(&proposal));
+        }
+        assert_eq!(reloaded.parameter_names, problem.parameter_names);
+
+        // transforms are checked against the parameters when reloading
+        let short = json.replace(
+            r#"["Softplus","Identity","Log"]"#,
+            r#"["Softplus","Identity"]"#,
+        );
+        assert_ne!(short, json);
+        let result: Result<InferenceProblem<BasicPrior, ObservationSet, InfluenceFunction>, _> =
+            serde_json::from_str(&short);
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("transform produces 2 values but there are 3 continuous parameters"));
+        let result = reloaded.try_with_transform(ParameterTransform::new(vec![Bijector::Log]));
+        assert_eq!(
+            result.err(),
+            Some(PriorError::TransformDimension {
+                transform: 1,
+                parameters: 3
+            })
+        );
+
+        // custom priors cannot be described
+        struct Flat;
+        impl priors::PartialPrior for Flat {
+            fn logprobability(&self, _proposed: &f32) -> f64 {
+                0.0
+            }
+
+            fn initial_guess(&self) -> f64 {
+                0.0
+            }
+        }
+        let custom = BasicPrior::new(vec![Box::new(Flat)]);
+        assert!(serde_json::to_string(&custom).is_err());
+    }
+
     #[test]
     fn sampler() {
         struct Model<'a> {
@@ -1,24 +1,270 @@
+use std::collections::BTreeMap;
+use std::f64::consts::{PI, SQRT_2};
+
+use emcee::Guess;
+use rand::{Rng, RngCore};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use statrs::distribution::{Cauchy, NegativeBinomial, Normal, Poisson, StudentsT};
+use statrs::function::erf::erfc;
+use statrs::function::gamma::ln_gamma;
+
+use crate::linalg::{cholesky, mvn_logdensity};
 use crate::models::Prediction;
+use crate::priors::PriorError;
 
 pub trait PartialLikelihood {
     fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64;
+
+    /// Log-likelihood for observations whose likelihood depends on
+    /// hyperparameters sampled in the proposal. Defaults to `loglikelihood`.
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+    ) -> f64 {
+        self.loglikelihood(observable, prediction_error, residual_error)
+    }
+
+    /// Probability that this observation is an outlier given the proposal, for
+    /// likelihoods that model outliers explicitly.
+    fn outlier_probability(
+        &self,
+        _observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+        _proposal: &Guess,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// A draw of the measured value given the prediction: `observable` plus
+    /// this observation's measurement noise under the proposal, for
+    /// posterior predictive intervals. Defaults to Gaussian noise with the
+    /// prediction and residual errors alone.
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        gaussian_sample(
+            *observable,
+            prediction_error.powi(2) + residual_error.powi(2),
+            rng,
+        )
+    }
+
+    /// Proposal indices of the hyperparameters sampled for this observation.
+    fn inferred_parameters(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// Proposal index of the error-inflation factor this observation shares
+    /// with its group, if any.
+    fn error_group(&self) -> Option<usize> {
+        None
+    }
+
+    /// Serializable description of this observation, if it is a built-in one.
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        None
+    }
+
+    /// The reported value, or the limit for observations censored at a
+    /// single limit, for computing residuals.
+    fn reported_value(&self) -> Option<f64> {
+        None
+    }
+
+    /// Whether `reported_value` is a censoring limit rather than a measured
+    /// value.
+    fn is_censored(&self) -> bool {
+        false
+    }
+}
+
+/// Tagged description of the built-in observation types, used to save and
+/// reload an `ObservationSet`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PartialLikelihoodSpec {
+    Observation(Observation),
+    Nondetect(NondetectObservation),
+    LeftCensored(LeftCensoredObservation),
+    IntervalCensored(IntervalCensoredObservation),
+    RightCensored(RightCensoredObservation),
+    LogNormal(LogNormalObservation),
+    LogNormalLeftCensored(LogNormalLeftCensoredObservation),
+    LogNormalIntervalCensored(LogNormalIntervalCensoredObservation),
+    LogNormalRightCensored(LogNormalRightCensoredObservation),
+    StudentT(StudentTObservation),
+    Cauchy(CauchyObservation),
+    OutlierMixture(OutlierMixtureObservation),
+    Poisson(PoissonObservation),
+    NegativeBinomial(NegativeBinomialObservation),
+}
+
+impl PartialLikelihoodSpec {
+    pub fn build(self) -> Box<dyn PartialLikelihood> {
+        match self {
+            PartialLikelihoodSpec::Observation(o) => Box::new(o),
+            PartialLikelihoodSpec::Nondetect(o) => Box::new(o),
+            PartialLikelihoodSpec::LeftCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::IntervalCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::RightCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::LogNormal(o) => Box::new(o),
+            PartialLikelihoodSpec::LogNormalLeftCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::LogNormalIntervalCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::LogNormalRightCensored(o) => Box::new(o),
+            PartialLikelihoodSpec::StudentT(o) => Box::new(o),
+            PartialLikelihoodSpec::Cauchy(o) => Box::new(o),
+            PartialLikelihoodSpec::OutlierMixture(o) => Box::new(o),
+            PartialLikelihoodSpec::Poisson(o) => Box::new(o),
+            PartialLikelihoodSpec::NegativeBinomial(o) => Box::new(o),
+        }
+    }
+}
+
+impl Serialize for dyn PartialLikelihood {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spec()
+            .ok_or_else(|| S::Error::custom("observation has no serializable description"))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn PartialLikelihood> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(PartialLikelihoodSpec::deserialize(deserializer)?.build())
+    }
 }
 
 pub trait Likelihood {
     fn loglikelihood(&self, prediction: Prediction) -> f64;
+
+    /// Log-likelihood with access to the proposal the prediction was made
+    /// from. Defaults to `loglikelihood`.
+    fn loglikelihood_with_proposal(&self, prediction: Prediction, _proposal: &Guess) -> f64 {
+        self.loglikelihood(prediction)
+    }
+
+    /// Proposal indices of the hyperparameters this likelihood samples.
+    fn inferred_parameters(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// Check that error-inflation groups are declared and sampled as the
+    /// parameters they are named after.
+    fn check_error_groups(&self, _parameter_names: &[String]) -> Result<(), PriorError> {
+        Ok(())
+    }
+}
+
+/// A likelihood hyperparameter that is either held fixed or sampled as the
+/// proposal entry at the given index.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Hyperparameter {
+    Fixed(f64),
+    Inferred(usize),
+}
+
+impl Hyperparameter {
+    /// `None` if the proposal has no entry at the inferred index.
+    pub fn value(&self, proposal: &Guess) -> Option<f64> {
+        match self {
+            Hyperparameter::Fixed(v) => Some(*v),
+            Hyperparameter::Inferred(i) => proposal.values.get(*i).map(|v| *v as f64),
+        }
+    }
+
+    /// `None` for inferred hyperparameters, which need a proposal.
+    fn fixed_value(&self) -> Option<f64> {
+        match self {
+            Hyperparameter::Fixed(v) => Some(*v),
+            Hyperparameter::Inferred(_) => None,
+        }
+    }
+
+    fn index(&self) -> Option<usize> {
+        match self {
+            Hyperparameter::Fixed(_) => None,
+            Hyperparameter::Inferred(i) => Some(*i),
+        }
+    }
 }
 
+/// Reported measurement uncertainty, either in the units of the observation
+/// or as a fraction of the reported value. Accepted by `from_error` on every
+/// Gaussian-family observation; log-normal observations take a geometric
+/// error and count observations take none.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ObservationError {
+    Absolute(f64),
+    Relative(f64),
+}
+
+impl ObservationError {
+    /// Absolute error for a reported `value`.
+    pub fn absolute(&self, value: f64) -> f64 {
+        match self {
+            ObservationError::Absolute(e) => *e,
+            ObservationError::Relative(r) => (r * value).abs(),
+        }
+    }
+}
+
+/// Fail with `message` unless `condition` holds.
+fn require(condition: bool, message: &str) -> Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(message.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Observation {
     value: f64,
     error: f64,
+    normalized: bool,
+    error_scale: Hyperparameter,
 }
 
 impl Observation {
+    /// Gaussian observation with a properly normalized log-density, so that
+    /// error terms (e.g. `Prediction::residual_error`) can be inferred.
     pub fn new(value: f64, error: f64) -> Self {
-        Self { value, error }
+        Self {
+            value,
+            error,
+            normalized: true,
+            error_scale: Hyperparameter::Fixed(1.0),
+        }
+    }
+
+    /// Gaussian observation with an absolute or relative reported error.
+    pub fn from_error(value: f64, error: ObservationError) -> Self {
+        Self::new(value, error.absolute(value))
+    }
+
+    /// Gaussian observation using the legacy `-(obs - value)^2 / variance` form.
+    /// Only suitable when all error terms are fixed.
+    pub fn new_unnormalized(value: f64, error: f64) -> Self {
+        Self {
+            value,
+            error,
+            normalized: false,
+            error_scale: Hyperparameter::Fixed(1.0),
+        }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NondetectObservation {
     detection_limit: f64,
     error: f64,
@@ -31,68 +277,2259 @@ impl NondetectObservation {
             error,
         }
     }
+
+    /// Nondetect with an absolute error or one relative to the detection limit.
+    pub fn from_error(detection_limit: f64, error: ObservationError) -> Self {
+        Self::new(detection_limit, error.absolute(detection_limit))
+    }
 }
 
-pub struct ObservationSet {
-    pub observations: Vec<Box<dyn PartialLikelihood>>,
+/// Nondetect treated as a left-censored (Tobit) observation: the likelihood
+/// is the probability that the measurement fell below the detection limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeftCensoredObservation {
+    detection_limit: f64,
+    error: f64,
+    error_scale: Hyperparameter,
 }
 
-impl ObservationSet {
-    pub fn new(observations: Vec<Box<dyn PartialLikelihood>>) -> Self {
-        Self { observations }
+impl LeftCensoredObservation {
+    pub fn new(detection_limit: f64, error: f64) -> Self {
+        Self {
+            detection_limit,
+            error,
+            error_scale: Hyperparameter::Fixed(1.0),
+        }
     }
 
-    pub fn add(&mut self, observation: Box<dyn PartialLikelihood>) {
-        self.observations.push(observation)
+    /// Nondetect with an absolute error or one relative to the detection limit.
+    pub fn from_error(detection_limit: f64, error: ObservationError) -> Self {
+        Self::new(detection_limit, error.absolute(detection_limit))
     }
 }
-impl Likelihood for ObservationSet {
-    fn loglikelihood(&self, prediction: Prediction) -> f64 {
-        let mut ll: f64 = 0.0;
-        for (i, (o, e)) in prediction
-            .observables
-            .into_iter()
-            .zip(prediction.errors)
-            .enumerate()
-        {
-            ll += self.observations[i].loglikelihood(&o, &e, &prediction.residual_error)
+
+/// Measurement reported only as lying between two limits, e.g. above the
+/// detection limit but below the quantitation limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "IntervalCensoredRaw")]
+pub struct IntervalCensoredObservation {
+    lower: f64,
+    upper: f64,
+    error: f64,
+    error_scale: Hyperparameter,
+}
+
+#[derive(Deserialize)]
+struct IntervalCensoredRaw {
+    lower: f64,
+    upper: f64,
+    error: f64,
+    error_scale: Hyperparameter,
+}
+
+impl TryFrom<IntervalCensoredRaw> for IntervalCensoredObservation {
+    type Error = String;
+
+    fn try_from(raw: IntervalCensoredRaw) -> Result<Self, String> {
+        Ok(Self {
+            error_scale: raw.error_scale,
+            ..Self::try_new(raw.lower, raw.upper, raw.error)?
+        })
+    }
+}
+
+impl IntervalCensoredObservation {
+    pub fn new(lower: f64, upper: f64, error: f64) -> Self {
+        Self::try_new(lower, upper, error).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(lower: f64, upper: f64, error: f64) -> Result<Self, String> {
+        require(
+            lower < upper,
+            "interval lower bound must be below upper bound",
+        )?;
+        Ok(Self {
+            lower,
+            upper,
+            error,
+            error_scale: Hyperparameter::Fixed(1.0),
+        })
+    }
+
+    /// Interval with an absolute error or one relative to the upper limit.
+    pub fn from_error(lower: f64, upper: f64, error: ObservationError) -> Self {
+        Self::new(lower, upper, error.absolute(upper))
+    }
+}
+
+/// Measurement reported only as exceeding a limit, e.g. a saturated
+/// instrument or a value above the calibration range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RightCensoredObservation {
+    limit: f64,
+    error: f64,
+    error_scale: Hyperparameter,
+}
+
+impl RightCensoredObservation {
+    pub fn new(limit: f64, error: f64) -> Self {
+        Self {
+            limit,
+            error,
+            error_scale: Hyperparameter::Fixed(1.0),
         }
-        ll
+    }
+
+    /// Censored observation with an absolute error or one relative to the limit.
+    pub fn from_error(limit: f64, error: ObservationError) -> Self {
+        Self::new(limit, error.absolute(limit))
     }
 }
 
-impl PartialLikelihood for Observation {
-    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
-        let total_error = &self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
-        -(observable - self.value).powi(2) / total_error
+/// Gaussian-family observations whose reported error can be multiplied by a
+/// shared, inferred factor.
+trait InflatableError {
+    fn error(&self) -> f64;
+
+    fn error_scale(&self) -> &Hyperparameter;
+
+    /// Log-likelihood given the total variance of the observation.
+    fn loglikelihood_with_variance(&self, observable: f64, variance: f64) -> f64;
+
+    /// Log-likelihood with the error scaled by the fixed factor, or by the
+    /// proposal entry if there is a proposal.
+    fn scaled_loglikelihood(
+        &self,
+        observable: f64,
+        prediction_error: f64,
+        residual_error: f64,
+        proposal: Option<&Guess>,
+    ) -> f64 {
+        let scale = match proposal {
+            Some(proposal) => self.error_scale().value(proposal),
+            None => self.error_scale().fixed_value(),
+        };
+        match scale {
+            Some(scale) if scale > 0.0 => {
+                let variance = (scale * self.error()).powi(2)
+                    + prediction_error.powi(2)
+                    + residual_error.powi(2);
+                self.loglikelihood_with_variance(observable, variance)
+            }
+            _ => f64::NEG_INFINITY,
+        }
+    }
+
+    /// Gaussian draw around `observable` with the error scaled by the
+    /// proposal entry, or unscaled if the entry is missing.
+    fn scaled_sample(
+        &self,
+        observable: f64,
+        prediction_error: f64,
+        residual_error: f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let scale = self.error_scale().value(proposal).unwrap_or(1.0);
+        let variance =
+            (scale * self.error()).powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        gaussian_sample(observable, variance, rng)
     }
 }
 
-impl PartialLikelihood for NondetectObservation {
-    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
-        if observable <= &self.detection_limit {
-            return 0.0;
+macro_rules! inflatable_error {
+    ($($observation:ty),*) => {$(
+        impl $observation {
+            /// Multiply the observation error by the proposal entry at
+            /// `index`, so a group of observations can share an inferred
+            /// error-inflation factor. Declare the group with
+            /// `ObservationSet::with_error_group`.
+            pub fn inflate_error(mut self, index: usize) -> Self {
+                self.error_scale = Hyperparameter::Inferred(index);
+                self
+            }
+        }
+    )*};
+}
+
+inflatable_error!(
+    Observation,
+    LeftCensoredObservation,
+    IntervalCensoredObservation,
+    RightCensoredObservation
+);
+
+impl InflatableError for Observation {
+    fn error(&self) -> f64 {
+        self.error
+    }
+
+    fn error_scale(&self) -> &Hyperparameter {
+        &self.error_scale
+    }
+
+    fn loglikelihood_with_variance(&self, observable: f64, variance: f64) -> f64 {
+        if self.normalized {
+            gaussian_loglikelihood(observable - self.value, variance)
         } else {
-            let total_error =
-                &self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
-            -(observable - self.detection_limit).powi(2) / total_error
+            -(observable - self.value).powi(2) / variance
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl InflatableError for LeftCensoredObservation {
+    fn error(&self) -> f64 {
+        self.error
+    }
 
-    #[test]
-    fn test_constructors() {
-        // let _ = Proposal::new(vec![0.0, 1.0, 3.0]);
-        let _ = Prediction::new(vec![0.0, 1.0, 3.0], vec![0.0, 0.0, 0.0], 0.0);
+    fn error_scale(&self) -> &Hyperparameter {
+        &self.error_scale
     }
 
-    #[test]
-    #[should_panic]
-    fn test_invalid_constructors() {
-        let _ = Prediction::new(vec![0.0], vec![0.0, 1.0], 0.0);
+    fn loglikelihood_with_variance(&self, observable: f64, variance: f64) -> f64 {
+        ln_normal_cdf((self.detection_limit - observable) / variance.sqrt())
+    }
+}
+
+impl InflatableError for IntervalCensoredObservation {
+    fn error(&self) -> f64 {
+        self.error
+    }
+
+    fn error_scale(&self) -> &Hyperparameter {
+        &self.error_scale
+    }
+
+    fn loglikelihood_with_variance(&self, observable: f64, variance: f64) -> f64 {
+        let sigma = variance.sqrt();
+        ln_normal_interval(
+            (self.lower - observable) / sigma,
+            (self.upper - observable) / sigma,
+        )
+    }
+}
+
+impl InflatableError for RightCensoredObservation {
+    fn error(&self) -> f64 {
+        self.error
+    }
+
+    fn error_scale(&self) -> &Hyperparameter {
+        &self.error_scale
+    }
+
+    fn loglikelihood_with_variance(&self, observable: f64, variance: f64) -> f64 {
+        ln_normal_cdf((observable - self.limit) / variance.sqrt())
+    }
+}
+
+/// Strictly positive observation compared with the prediction in log space.
+/// `geometric_error` is the multiplicative (geometric standard deviation)
+/// error, e.g. `1.5` for "within a factor of 1.5". Prediction errors are
+/// converted to log space relative to the prediction, and the residual error
+/// is taken to be a log-space standard deviation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LogNormalRaw")]
+pub struct LogNormalObservation {
+    value: f64,
+    geometric_error: f64,
+}
+
+#[derive(Deserialize)]
+struct LogNormalRaw {
+    value: f64,
+    geometric_error: f64,
+}
+
+impl TryFrom<LogNormalRaw> for LogNormalObservation {
+    type Error = String;
+
+    fn try_from(raw: LogNormalRaw) -> Result<Self, String> {
+        Self::try_new(raw.value, raw.geometric_error)
+    }
+}
+
+impl LogNormalObservation {
+    pub fn new(value: f64, geometric_error: f64) -> Self {
+        Self::try_new(value, geometric_error).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(value: f64, geometric_error: f64) -> Result<Self, String> {
+        require(value > 0.0, "log-normal observation must be positive")?;
+        require(geometric_error >= 1.0, "geometric error must be at least 1")?;
+        Ok(Self {
+            value,
+            geometric_error,
+        })
+    }
+}
+
+/// Log-normal counterpart of `LeftCensoredObservation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LogNormalLeftCensoredRaw")]
+pub struct LogNormalLeftCensoredObservation {
+    detection_limit: f64,
+    geometric_error: f64,
+}
+
+#[derive(Deserialize)]
+struct LogNormalLeftCensoredRaw {
+    detection_limit: f64,
+    geometric_error: f64,
+}
+
+impl TryFrom<LogNormalLeftCensoredRaw> for LogNormalLeftCensoredObservation {
+    type Error = String;
+
+    fn try_from(raw: LogNormalLeftCensoredRaw) -> Result<Self, String> {
+        Self::try_new(raw.detection_limit, raw.geometric_error)
+    }
+}
+
+impl LogNormalLeftCensoredObservation {
+    pub fn new(detection_limit: f64, geometric_error: f64) -> Self {
+        Self::try_new(detection_limit, geometric_error).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(detection_limit: f64, geometric_error: f64) -> Result<Self, String> {
+        require(detection_limit > 0.0, "detection limit must be positive")?;
+        require(geometric_error >= 1.0, "geometric error must be at least 1")?;
+        Ok(Self {
+            detection_limit,
+            geometric_error,
+        })
+    }
+}
+
+/// Log-normal counterpart of `IntervalCensoredObservation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LogNormalIntervalCensoredRaw")]
+pub struct LogNormalIntervalCensoredObservation {
+    lower: f64,
+    upper: f64,
+    geometric_error: f64,
+}
+
+#[derive(Deserialize)]
+struct LogNormalIntervalCensoredRaw {
+    lower: f64,
+    upper: f64,
+    geometric_error: f64,
+}
+
+impl TryFrom<LogNormalIntervalCensoredRaw> for LogNormalIntervalCensoredObservation {
+    type Error = String;
+
+    fn try_from(raw: LogNormalIntervalCensoredRaw) -> Result<Self, String> {
+        Self::try_new(raw.lower, raw.upper, raw.geometric_error)
+    }
+}
+
+impl LogNormalIntervalCensoredObservation {
+    pub fn new(lower: f64, upper: f64, geometric_error: f64) -> Self {
+        Self::try_new(lower, upper, geometric_error).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(lower: f64, upper: f64, geometric_error: f64) -> Result<Self, String> {
+        require(lower > 0.0, "interval lower bound must be positive")?;
+        require(
+            lower < upper,
+            "interval lower bound must be below upper bound",
+        )?;
+        require(geometric_error >= 1.0, "geometric error must be at least 1")?;
+        Ok(Self {
+            lower,
+            upper,
+            geometric_error,
+        })
+    }
+}
+
+/// Log-normal counterpart of `RightCensoredObservation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LogNormalRightCensoredRaw")]
+pub struct LogNormalRightCensoredObservation {
+    limit: f64,
+    geometric_error: f64,
+}
+
+#[derive(Deserialize)]
+struct LogNormalRightCensoredRaw {
+    limit: f64,
+    geometric_error: f64,
+}
+
+impl TryFrom<LogNormalRightCensoredRaw> for LogNormalRightCensoredObservation {
+    type Error = String;
+
+    fn try_from(raw: LogNormalRightCensoredRaw) -> Result<Self, String> {
+        Self::try_new(raw.limit, raw.geometric_error)
+    }
+}
+
+impl LogNormalRightCensoredObservation {
+    pub fn new(limit: f64, geometric_error: f64) -> Self {
+        Self::try_new(limit, geometric_error).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(limit: f64, geometric_error: f64) -> Result<Self, String> {
+        require(limit > 0.0, "limit must be positive")?;
+        require(geometric_error >= 1.0, "geometric error must be at least 1")?;
+        Ok(Self {
+            limit,
+            geometric_error,
+        })
+    }
+}
+
+/// Heavy-tailed Student-t observation for robustness against outliers. The
+/// combined errors set the scale of the distribution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "StudentTRaw")]
+pub struct StudentTObservation {
+    value: f64,
+    error: f64,
+    degrees_of_freedom: Hyperparameter,
+}
+
+#[derive(Deserialize)]
+struct StudentTRaw {
+    value: f64,
+    error: f64,
+    degrees_of_freedom: Hyperparameter,
+}
+
+impl TryFrom<StudentTRaw> for StudentTObservation {
+    type Error = String;
+
+    fn try_from(raw: StudentTRaw) -> Result<Self, String> {
+        match raw.degrees_of_freedom {
+            Hyperparameter::Fixed(nu) => Self::try_new(raw.value, raw.error, nu),
+            Hyperparameter::Inferred(i) => Ok(Self::new_inferred(raw.value, raw.error, i)),
+        }
+    }
+}
+
+impl StudentTObservation {
+    pub fn new(value: f64, error: f64, degrees_of_freedom: f64) -> Self {
+        Self::try_new(value, error, degrees_of_freedom).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(value: f64, error: f64, degrees_of_freedom: f64) -> Result<Self, String> {
+        require(
+            degrees_of_freedom > 0.0,
+            "degrees of freedom must be positive",
+        )?;
+        Ok(Self {
+            value,
+            error,
+            degrees_of_freedom: Hyperparameter::Fixed(degrees_of_freedom),
+        })
+    }
+
+    /// See `Observation::from_error`.
+    pub fn from_error(value: f64, error: ObservationError, degrees_of_freedom: f64) -> Self {
+        Self::new(value, error.absolute(value), degrees_of_freedom)
+    }
+
+    /// Student-t observation whose degrees of freedom are sampled as the
+    /// proposal entry at `index`.
+    pub fn new_inferred(value: f64, error: f64, index: usize) -> Self {
+        Self {
+            value,
+            error,
+            degrees_of_freedom: Hyperparameter::Inferred(index),
+        }
+    }
+
+    fn loglikelihood_dof(
+        &self,
+        observable: f64,
+        prediction_error: f64,
+        residual_error: f64,
+        nu: f64,
+    ) -> f64 {
+        if nu <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        let z2 = (observable - self.value).powi(2) / total_error;
+        ln_gamma(0.5 * (nu + 1.0))
+            - ln_gamma(0.5 * nu)
+            - 0.5 * (nu * PI * total_error).ln()
+            - 0.5 * (nu + 1.0) * (z2 / nu).ln_1p()
+    }
+}
+
+/// Cauchy observation, the one degree of freedom limit of
+/// `StudentTObservation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CauchyObservation {
+    value: f64,
+    error: f64,
+}
+
+impl CauchyObservation {
+    pub fn new(value: f64, error: f64) -> Self {
+        Self { value, error }
+    }
+
+    /// See `Observation::from_error`.
+    pub fn from_error(value: f64, error: ObservationError) -> Self {
+        Self::new(value, error.absolute(value))
+    }
+}
+
+/// Gaussian observation that may instead come from a broad outlier
+/// distribution with probability `outlier_fraction`. The outlier component
+/// adds `outlier_error` in quadrature to the usual combined error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "OutlierMixtureRaw")]
+pub struct OutlierMixtureObservation {
+    value: f64,
+    error: f64,
+    outlier_error: f64,
+    outlier_fraction: Hyperparameter,
+}
+
+#[derive(Deserialize)]
+struct OutlierMixtureRaw {
+    value: f64,
+    error: f64,
+    outlier_error: f64,
+    outlier_fraction: Hyperparameter,
+}
+
+impl TryFrom<OutlierMixtureRaw> for OutlierMixtureObservation {
+    type Error = String;
+
+    fn try_from(raw: OutlierMixtureRaw) -> Result<Self, String> {
+        match raw.outlier_fraction {
+            Hyperparameter::Fixed(fraction) => {
+                Self::try_new(raw.value, raw.error, raw.outlier_error, fraction)
+            }
+            Hyperparameter::Inferred(i) => Ok(Self::new_inferred(
+                raw.value,
+                raw.error,
+                raw.outlier_error,
+                i,
+            )),
+        }
+    }
+}
+
+impl OutlierMixtureObservation {
+    pub fn new(value: f64, error: f64, outlier_error: f64, outlier_fraction: f64) -> Self {
+        Self::try_new(value, error, outlier_error, outlier_fraction)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(
+        value: f64,
+        error: f64,
+        outlier_error: f64,
+        outlier_fraction: f64,
+    ) -> Result<Self, String> {
+        require(
+            (0.0..=1.0).contains(&outlier_fraction),
+            "outlier fraction must be in [0, 1]",
+        )?;
+        Ok(Self {
+            value,
+            error,
+            outlier_error,
+            outlier_fraction: Hyperparameter::Fixed(outlier_fraction),
+        })
+    }
+
+    /// See `Observation::from_error`. Only the reported error may be
+    /// relative; `outlier_error` is always absolute.
+    pub fn from_error(
+        value: f64,
+        error: ObservationError,
+        outlier_error: f64,
+        outlier_fraction: f64,
+    ) -> Self {
+        Self::new(
+            value,
+            error.absolute(value),
+            outlier_error,
+            outlier_fraction,
+        )
+    }
+
+    /// Mixture observation whose global outlier fraction is sampled as the
+    /// proposal entry at `index`.
+    pub fn new_inferred(value: f64, error: f64, outlier_error: f64, index: usize) -> Self {
+        Self {
+            value,
+            error,
+            outlier_error,
+            outlier_fraction: Hyperparameter::Inferred(index),
+        }
+    }
+
+    /// Log-likelihoods of the good and outlier components, each weighted by
+    /// its mixture fraction.
+    fn weighted_components(
+        &self,
+        observable: f64,
+        prediction_error: f64,
+        residual_error: f64,
+        fraction: Option<f64>,
+    ) -> (f64, f64) {
+        let fraction = match fraction {
+            Some(f) if (0.0..=1.0).contains(&f) => f,
+            _ => return (f64::NEG_INFINITY, f64::NEG_INFINITY),
+        };
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        let residual = observable - self.value;
+        (
+            (1.0 - fraction).ln() + gaussian_loglikelihood(residual, total_error),
+            fraction.ln()
+                + gaussian_loglikelihood(residual, total_error + self.outlier_error.powi(2)),
+        )
+    }
+}
+
+/// Count observation with the prediction interpreted as the expected count.
+/// Prediction and residual errors are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoissonObservation {
+    count: u64,
+}
+
+impl PoissonObservation {
+    pub fn new(count: u64) -> Self {
+        Self { count }
+    }
+}
+
+/// Overdispersed count observation with the prediction interpreted as the
+/// mean count. `dispersion` is the negative-binomial size parameter: the
+/// variance is `mean + mean^2 / dispersion`, approaching Poisson as it grows.
+/// Prediction and residual errors are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "NegativeBinomialRaw")]
+pub struct NegativeBinomialObservation {
+    count: u64,
+    dispersion: Hyperparameter,
+}
+
+#[derive(Deserialize)]
+struct NegativeBinomialRaw {
+    count: u64,
+    dispersion: Hyperparameter,
+}
+
+impl TryFrom<NegativeBinomialRaw> for NegativeBinomialObservation {
+    type Error = String;
+
+    fn try_from(raw: NegativeBinomialRaw) -> Result<Self, String> {
+        match raw.dispersion {
+            Hyperparameter::Fixed(dispersion) => Self::try_new(raw.count, dispersion),
+            Hyperparameter::Inferred(i) => Ok(Self::new_inferred(raw.count, i)),
+        }
+    }
+}
+
+impl NegativeBinomialObservation {
+    pub fn new(count: u64, dispersion: f64) -> Self {
+        Self::try_new(count, dispersion).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(count: u64, dispersion: f64) -> Result<Self, String> {
+        require(dispersion > 0.0, "dispersion must be positive")?;
+        Ok(Self {
+            count,
+            dispersion: Hyperparameter::Fixed(dispersion),
+        })
+    }
+
+    /// Negative-binomial observation whose dispersion is sampled as the
+    /// proposal entry at `index`.
+    pub fn new_inferred(count: u64, index: usize) -> Self {
+        Self {
+            count,
+            dispersion: Hyperparameter::Inferred(index),
+        }
+    }
+
+    fn loglikelihood_dispersion(&self, mean: f64, dispersion: f64) -> f64 {
+        if dispersion <= 0.0 || mean < 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let k = self.count as f64;
+        if mean == 0.0 {
+            return if self.count == 0 {
+                0.0
+            } else {
+                f64::NEG_INFINITY
+            };
+        }
+        ln_gamma(k + dispersion) - ln_gamma(dispersion) - ln_gamma(k + 1.0)
+            + dispersion * (dispersion / (dispersion + mean)).ln()
+            + k * (mean / (dispersion + mean)).ln()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ObservationSet {
+    pub observations: Vec<Box<dyn PartialLikelihood>>,
+    #[serde(default)]
+    provenance: Vec<Provenance>,
+    /// Proposal index of each declared error-inflation group, by parameter
+    /// name.
+    #[serde(default)]
+    error_groups: BTreeMap<String, usize>,
+}
+
+/// Where an observation came from, carried through to diagnostics so that
+/// reports can refer to the original samples.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub sample_id: Option<String>,
+    pub cruise_id: Option<String>,
+    /// Other columns of the input row, by column name.
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl ObservationSet {
+    pub fn new(observations: Vec<Box<dyn PartialLikelihood>>) -> Self {
+        let provenance = vec![Provenance::default(); observations.len()];
+        Self {
+            observations,
+            provenance,
+            error_groups: BTreeMap::new(),
+        }
+    }
+
+    /// Declare an error-inflation group whose factor is sampled as the
+    /// parameter `name` at proposal `index`. Observations join the group
+    /// with `inflate_error(index)`.
+    pub fn with_error_group(mut self, name: &str, index: usize) -> Self {
+        self.error_groups.insert(name.to_string(), index);
+        self
+    }
+
+    /// Attach one `Provenance` per observation, in order.
+    pub fn with_provenance(mut self, provenance: Vec<Provenance>) -> Self {
+        assert_eq!(
+            provenance.len(),
+            self.observations.len(),
+            "need one provenance per observation"
+        );
+        self.provenance = provenance;
+        self
+    }
+
+    pub fn add(&mut self, observation: Box<dyn PartialLikelihood>) {
+        self.add_with_provenance(observation, Provenance::default())
+    }
+
+    pub fn add_with_provenance(
+        &mut self,
+        observation: Box<dyn PartialLikelihood>,
+        provenance: Provenance,
+    ) {
+        self.observations.push(observation);
+        self.provenance
+            .resize(self.observations.len() - 1, Provenance::default());
+        self.provenance.push(provenance);
+    }
+
+    /// Provenance of the observation at `index`; empty for observations
+    /// added without one.
+    pub fn provenance(&self, index: usize) -> Provenance {
+        self.provenance.get(index).cloned().unwrap_or_default()
+    }
+
+    /// Log-likelihood of each observation for a single proposal.
+    pub fn observation_loglikelihoods(&self, prediction: Prediction, proposal: &Guess) -> Vec<f64> {
+        self.observations
+            .iter()
+            .zip(prediction.observables.iter().zip(prediction.errors.iter()))
+            .map(|(obs, (o, e))| {
+                obs.loglikelihood_with_proposal(o, e, &prediction.residual_error, proposal)
+            })
+            .collect()
+    }
+
+    /// One posterior predictive draw of each measured value for a single
+    /// proposal.
+    pub fn predictive_samples(
+        &self,
+        prediction: &Prediction,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> Vec<f64> {
+        self.observations
+            .iter()
+            .zip(prediction.observables.iter().zip(prediction.errors.iter()))
+            .map(|(obs, (o, e))| {
+                obs.predictive_sample(o, e, &prediction.residual_error, proposal, rng)
+            })
+            .collect()
+    }
+
+    /// Per-observation outlier probabilities for a single proposal; `None`
+    /// for observations that do not model outliers.
+    pub fn outlier_probabilities(
+        &self,
+        prediction: Prediction,
+        proposal: &Guess,
+    ) -> Vec<Option<f64>> {
+        self.observations
+            .iter()
+            .zip(prediction.observables.iter().zip(prediction.errors.iter()))
+            .map(|(obs, (o, e))| {
+                obs.outlier_probability(o, e, &prediction.residual_error, proposal)
+            })
+            .collect()
+    }
+}
+impl Likelihood for ObservationSet {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.observations
+            .iter()
+            .flat_map(|o| o.inferred_parameters())
+            .collect()
+    }
+
+    fn check_error_groups(&self, parameter_names: &[String]) -> Result<(), PriorError> {
+        for (group, index) in &self.error_groups {
+            if parameter_names.get(*index) != Some(group) {
+                return Err(PriorError::ErrorGroupMismatch {
+                    group: group.clone(),
+                    index: *index,
+                });
+            }
+        }
+        match self
+            .observations
+            .iter()
+            .filter_map(|o| o.error_group())
+            .find(|i| !self.error_groups.values().any(|g| g == i))
+        {
+            Some(index) => Err(PriorError::UndeclaredErrorGroup(index)),
+            None => Ok(()),
+        }
+    }
+
+    fn loglikelihood(&self, prediction: Prediction) -> f64 {
+        let mut ll: f64 = 0.0;
+        for (i, (o, e)) in prediction
+            .observables
+            .into_iter()
+            .zip(prediction.errors)
+            .enumerate()
+        {
+            ll += self.observations[i].loglikelihood(&o, &e, &prediction.residual_error)
+        }
+        ll
+    }
+
+    fn loglikelihood_with_proposal(&self, prediction: Prediction, proposal: &Guess) -> f64 {
+        let mut ll: f64 = 0.0;
+        for (i, (o, e)) in prediction
+            .observables
+            .into_iter()
+            .zip(prediction.errors)
+            .enumerate()
+        {
+            ll += self.observations[i].loglikelihood_with_proposal(
+                &o,
+                &e,
+                &prediction.residual_error,
+                proposal,
+            )
+        }
+        ll
+    }
+}
+
+fn gaussian_sample(mean: f64, variance: f64, rng: &mut dyn RngCore) -> f64 {
+    mean + variance.sqrt() * rng.sample::<f64, _>(Normal::standard())
+}
+
+fn gaussian_loglikelihood(residual: f64, variance: f64) -> f64 {
+    -0.5 * (residual.powi(2) / variance + (2.0 * PI * variance).ln())
+}
+
+impl PartialLikelihood for Observation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.error_scale.index().into_iter().collect()
+    }
+
+    fn error_group(&self) -> Option<usize> {
+        self.error_scale.index()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::Observation(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        self.scaled_loglikelihood(*observable, *prediction_error, *residual_error, None)
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.scaled_loglikelihood(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            Some(proposal),
+        )
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        self.scaled_sample(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            proposal,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for NondetectObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::Nondetect(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.detection_limit)
+    }
+
+    fn is_censored(&self) -> bool {
+        true
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        if observable <= &self.detection_limit {
+            0.0
+        } else {
+            let total_error =
+                self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+            -(observable - self.detection_limit).powi(2) / total_error
+        }
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        gaussian_sample(*observable, total_error, rng)
+    }
+}
+
+impl PartialLikelihood for LeftCensoredObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.error_scale.index().into_iter().collect()
+    }
+
+    fn error_group(&self) -> Option<usize> {
+        self.error_scale.index()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::LeftCensored(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.detection_limit)
+    }
+
+    fn is_censored(&self) -> bool {
+        true
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        self.scaled_loglikelihood(*observable, *prediction_error, *residual_error, None)
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.scaled_loglikelihood(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            Some(proposal),
+        )
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        self.scaled_sample(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            proposal,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for IntervalCensoredObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.error_scale.index().into_iter().collect()
+    }
+
+    fn error_group(&self) -> Option<usize> {
+        self.error_scale.index()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::IntervalCensored(self.clone()))
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        self.scaled_loglikelihood(*observable, *prediction_error, *residual_error, None)
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.scaled_loglikelihood(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            Some(proposal),
+        )
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        self.scaled_sample(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            proposal,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for RightCensoredObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.error_scale.index().into_iter().collect()
+    }
+
+    fn error_group(&self) -> Option<usize> {
+        self.error_scale.index()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::RightCensored(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.limit)
+    }
+
+    fn is_censored(&self) -> bool {
+        true
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        self.scaled_loglikelihood(*observable, *prediction_error, *residual_error, None)
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.scaled_loglikelihood(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            Some(proposal),
+        )
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        self.scaled_sample(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            proposal,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for StudentTObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.degrees_of_freedom.index().into_iter().collect()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::StudentT(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        self.degrees_of_freedom
+            .fixed_value()
+            .map_or(f64::NEG_INFINITY, |nu| {
+                self.loglikelihood_dof(*observable, *prediction_error, *residual_error, nu)
+            })
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.degrees_of_freedom
+            .value(proposal)
+            .map_or(f64::NEG_INFINITY, |nu| {
+                self.loglikelihood_dof(*observable, *prediction_error, *residual_error, nu)
+            })
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        self.degrees_of_freedom
+            .value(proposal)
+            .and_then(|nu| StudentsT::new(*observable, total_error.sqrt(), nu).ok())
+            .map_or(*observable, |d| rng.sample(d))
+    }
+}
+
+impl PartialLikelihood for CauchyObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::Cauchy(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        -(PI * total_error.sqrt()).ln() - ((observable - self.value).powi(2) / total_error).ln_1p()
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let total_error = self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        Cauchy::new(*observable, total_error.sqrt()).map_or(*observable, |d| rng.sample(d))
+    }
+}
+
+impl PartialLikelihood for OutlierMixtureObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.outlier_fraction.index().into_iter().collect()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::OutlierMixture(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        let (good, bad) = self.weighted_components(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            self.outlier_fraction.fixed_value(),
+        );
+        log_sum_exp(good, bad)
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        let (good, bad) = self.weighted_components(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            self.outlier_fraction.value(proposal),
+        );
+        log_sum_exp(good, bad)
+    }
+
+    fn outlier_probability(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+    ) -> Option<f64> {
+        let (good, bad) = self.weighted_components(
+            *observable,
+            *prediction_error,
+            *residual_error,
+            self.outlier_fraction.value(proposal),
+        );
+        let total = log_sum_exp(good, bad);
+        if total.is_finite() {
+            Some((bad - total).exp())
+        } else {
+            None
+        }
+    }
+
+    /// Draws from the outlier component with the sampled outlier fraction.
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let mut total_error =
+            self.error.powi(2) + prediction_error.powi(2) + residual_error.powi(2);
+        let fraction = self.outlier_fraction.value(proposal).unwrap_or(0.0);
+        if rng.gen::<f64>() < fraction {
+            total_error += self.outlier_error.powi(2);
+        }
+        gaussian_sample(*observable, total_error, rng)
+    }
+}
+
+fn log_sum_exp(a: f64, b: f64) -> f64 {
+    let max = a.max(b);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+impl PartialLikelihood for PoissonObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::Poisson(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.count as f64)
+    }
+
+    fn loglikelihood(
+        &self,
+        observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+    ) -> f64 {
+        if *observable < 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        if *observable == 0.0 {
+            return if self.count == 0 {
+                0.0
+            } else {
+                f64::NEG_INFINITY
+            };
+        }
+        let k = self.count as f64;
+        k * observable.ln() - observable - ln_gamma(k + 1.0)
+    }
+
+    /// A Poisson count with the prediction as its mean.
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        Poisson::new(*observable).map_or(0.0, |d| rng.sample(d))
+    }
+}
+
+impl PartialLikelihood for NegativeBinomialObservation {
+    fn inferred_parameters(&self) -> Vec<usize> {
+        self.dispersion.index().into_iter().collect()
+    }
+
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::NegativeBinomial(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.count as f64)
+    }
+
+    fn loglikelihood(
+        &self,
+        observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+    ) -> f64 {
+        self.dispersion
+            .fixed_value()
+            .map_or(f64::NEG_INFINITY, |dispersion| {
+                self.loglikelihood_dispersion(*observable, dispersion)
+            })
+    }
+
+    fn loglikelihood_with_proposal(
+        &self,
+        observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+        proposal: &Guess,
+    ) -> f64 {
+        self.dispersion
+            .value(proposal)
+            .map_or(f64::NEG_INFINITY, |dispersion| {
+                self.loglikelihood_dispersion(*observable, dispersion)
+            })
+    }
+
+    /// A negative-binomial count with the prediction as its mean.
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        _prediction_error: &f64,
+        _residual_error: &f64,
+        proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        self.dispersion
+            .value(proposal)
+            .and_then(|r| NegativeBinomial::new(r, r / (r + observable)).ok())
+            .map_or(*observable, |d| rng.sample::<u64, _>(d) as f64)
+    }
+}
+
+/// Observation error covariance for `CorrelatedObservationSet`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Covariance {
+    /// Fully specified covariance matrix.
+    Full(Vec<Vec<f64>>),
+    /// Observations in the same group (e.g. the same cruise) are correlated
+    /// with coefficient `correlation`; different groups are independent.
+    Grouped {
+        errors: Vec<f64>,
+        groups: Vec<usize>,
+        correlation: f64,
+    },
+    /// Correlation decays as `exp(-distance / length_scale)` with the
+    /// Euclidean distance between observation coordinates (time, position).
+    ExponentialDecay {
+        errors: Vec<f64>,
+        coordinates: Vec<Vec<f64>>,
+        length_scale: f64,
+    },
+}
+
+impl Covariance {
+    pub fn matrix(&self) -> Vec<Vec<f64>> {
+        match self {
+            Covariance::Full(matrix) => matrix.clone(),
+            Covariance::Grouped {
+                errors,
+                groups,
+                correlation,
+            } => {
+                assert_eq!(errors.len(), groups.len());
+                correlation_matrix(errors, |i, j| {
+                    if i == j {
+                        1.0
+                    } else if groups[i] == groups[j] {
+                        *correlation
+                    } else {
+                        0.0
+                    }
+                })
+            }
+            Covariance::ExponentialDecay {
+                errors,
+                coordinates,
+                length_scale,
+            } => {
+                assert_eq!(errors.len(), coordinates.len());
+                correlation_matrix(errors, |i, j| {
+                    let distance = coordinates[i]
+                        .iter()
+                        .zip(&coordinates[j])
+                        .map(|(a, b)| (a - b).powi(2))
+                        .sum::<f64>()
+                        .sqrt();
+                    (-distance / length_scale).exp()
+                })
+            }
+        }
+    }
+}
+
+fn correlation_matrix(errors: &[f64], correlation: impl Fn(usize, usize) -> f64) -> Vec<Vec<f64>> {
+    (0..errors.len())
+        .map(|i| {
+            (0..errors.len())
+                .map(|j| errors[i] * errors[j] * correlation(i, j))
+                .collect()
+        })
+        .collect()
+}
+
+/// Jointly Gaussian observations with correlated errors. The prediction
+/// and residual errors are added to the diagonal of the observation
+/// covariance before evaluating the multivariate normal density.
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "CorrelatedObservationSetRaw")]
+pub struct CorrelatedObservationSet {
+    values: Vec<f64>,
+    covariance: Vec<Vec<f64>>,
+}
+
+#[derive(Deserialize)]
+struct CorrelatedObservationSetRaw {
+    values: Vec<f64>,
+    covariance: Vec<Vec<f64>>,
+}
+
+impl TryFrom<CorrelatedObservationSetRaw> for CorrelatedObservationSet {
+    type Error = String;
+
+    fn try_from(raw: CorrelatedObservationSetRaw) -> Result<Self, String> {
+        Self::try_new(raw.values, raw.covariance)
+    }
+}
+
+impl CorrelatedObservationSet {
+    pub fn new(values: Vec<f64>, covariance: Covariance) -> Self {
+        Self::try_new(values, covariance.matrix()).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_new(values: Vec<f64>, covariance: Vec<Vec<f64>>) -> Result<Self, String> {
+        require(
+            covariance.len() == values.len()
+                && covariance.iter().all(|row| row.len() == values.len()),
+            "need one covariance row and column per value",
+        )?;
+        require(is_symmetric(&covariance), "covariance must be symmetric")?;
+        Ok(Self { values, covariance })
+    }
+}
+
+fn is_symmetric(matrix: &[Vec<f64>]) -> bool {
+    (0..matrix.len()).all(|i| {
+        (0..i).all(|j| {
+            let (a, b) = (matrix[i][j], matrix[j][i]);
+            (a - b).abs() <= 1e-12 * a.abs().max(b.abs()).max(1.0)
+        })
+    })
+}
+
+impl Likelihood for CorrelatedObservationSet {
+    /// `-inf` if the prediction does not have one value per observation.
+    fn loglikelihood(&self, prediction: Prediction) -> f64 {
+        if prediction.observables.len() != self.values.len() {
+            return f64::NEG_INFINITY;
+        }
+        let mut covariance = self.covariance.clone();
+        for (i, e) in prediction.errors.iter().enumerate() {
+            covariance[i][i] += e.powi(2) + prediction.residual_error.powi(2);
+        }
+        let residual: Vec<f64> = prediction
+            .observables
+            .iter()
+            .zip(&self.values)
+            .map(|(o, v)| o - v)
+            .collect();
+        match cholesky(&covariance) {
+            Some(lower) => mvn_logdensity(&lower, &residual),
+            None => f64::NEG_INFINITY,
+        }
+    }
+}
+
+/// Log-space standard deviation combining a geometric observation error with
+/// the prediction's absolute and residual errors.
+fn log_space_error(
+    geometric_error: f64,
+    observable: f64,
+    prediction_error: f64,
+    residual_error: f64,
+) -> f64 {
+    (geometric_error.ln().powi(2)
+        + (prediction_error / observable).powi(2)
+        + residual_error.powi(2))
+    .sqrt()
+}
+
+/// Log-normal draw around a positive `observable` with the error of
+/// `log_space_error`; other predictions are returned as they are.
+fn log_normal_sample(
+    geometric_error: f64,
+    observable: f64,
+    prediction_error: f64,
+    residual_error: f64,
+    rng: &mut dyn RngCore,
+) -> f64 {
+    if observable <= 0.0 {
+        return observable;
+    }
+    let sigma = log_space_error(
+        geometric_error,
+        observable,
+        prediction_error,
+        residual_error,
+    );
+    gaussian_sample(observable.ln(), sigma.powi(2), rng).exp()
+}
+
+impl PartialLikelihood for LogNormalObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::LogNormal(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        if *observable <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let sigma = log_space_error(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+        );
+        let ln_value = self.value.ln();
+        gaussian_loglikelihood(observable.ln() - ln_value, sigma.powi(2)) - ln_value
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        log_normal_sample(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for LogNormalLeftCensoredObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::LogNormalLeftCensored(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.detection_limit)
+    }
+
+    fn is_censored(&self) -> bool {
+        true
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        if *observable <= 0.0 {
+            return 0.0;
+        }
+        let sigma = log_space_error(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+        );
+        ln_normal_cdf((self.detection_limit.ln() - observable.ln()) / sigma)
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        log_normal_sample(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for LogNormalIntervalCensoredObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::LogNormalIntervalCensored(
+            self.clone(),
+        ))
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        if *observable <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let sigma = log_space_error(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+        );
+        ln_normal_interval(
+            (self.lower.ln() - observable.ln()) / sigma,
+            (self.upper.ln() - observable.ln()) / sigma,
+        )
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        log_normal_sample(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+            rng,
+        )
+    }
+}
+
+impl PartialLikelihood for LogNormalRightCensoredObservation {
+    fn spec(&self) -> Option<PartialLikelihoodSpec> {
+        Some(PartialLikelihoodSpec::LogNormalRightCensored(self.clone()))
+    }
+
+    fn reported_value(&self) -> Option<f64> {
+        Some(self.limit)
+    }
+
+    fn is_censored(&self) -> bool {
+        true
+    }
+
+    fn loglikelihood(&self, observable: &f64, prediction_error: &f64, residual_error: &f64) -> f64 {
+        if *observable <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let sigma = log_space_error(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+        );
+        ln_normal_cdf((observable.ln() - self.limit.ln()) / sigma)
+    }
+
+    fn predictive_sample(
+        &self,
+        observable: &f64,
+        prediction_error: &f64,
+        residual_error: &f64,
+        _proposal: &Guess,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        log_normal_sample(
+            self.geometric_error,
+            *observable,
+            *prediction_error,
+            *residual_error,
+            rng,
+        )
+    }
+}
+
+/// Natural log of the standard normal probability mass between `a` and `b`
+/// (`a < b`), evaluated in whichever tail keeps the subtraction accurate.
+fn ln_normal_interval(a: f64, b: f64) -> f64 {
+    let (a, b) = if a > 0.0 { (-b, -a) } else { (a, b) };
+    let ln_upper = ln_normal_cdf(b);
+    ln_upper + (-(ln_normal_cdf(a) - ln_upper).exp()).ln_1p()
+}
+
+/// Natural log of the standard normal CDF. Far in the lower tail, where
+/// `erfc` underflows, this uses the asymptotic expansion instead, blending
+/// the two linearly over `[-35, -30]` so the result stays continuous.
+fn ln_normal_cdf(z: f64) -> f64 {
+    const BLEND_START: f64 = -30.0;
+    const BLEND_END: f64 = -35.0;
+    let direct = || (0.5 * erfc(-z / SQRT_2)).ln();
+    if z >= BLEND_START {
+        return direct();
+    }
+    let z2 = z.powi(2);
+    let series = 1.0 - 1.0 / z2 + 3.0 / z2.powi(2) - 15.0 / z2.powi(3);
+    let asymptotic = -0.5 * z2 - (-z).ln() - 0.5 * (2.0 * PI).ln() + series.ln();
+    if z <= BLEND_END {
+        return asymptotic;
+    }
+    let weight = (z - BLEND_END) / (BLEND_START - BLEND_END);
+    weight * direct() + (1.0 - weight) * asymptotic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statrs::assert_almost_eq;
+    use statrs::distribution::{
+        Cauchy, Continuous, ContinuousCDF, Discrete, LogNormal, NegativeBinomial, Normal, Poisson,
+        StudentsT,
+    };
+
+    #[test]
+    fn test_constructors() {
+        // let _ = Proposal::new(vec![0.0, 1.0, 3.0]);
+        let _ = Prediction::new(vec![0.0, 1.0, 3.0], vec![0.0, 0.0, 0.0], 0.0);
+    }
+
+    #[test]
+    fn invalid_observation_json() {
+        // deserialization goes through the constructors' checks
+        for invalid in [
+            r#"{"type": "IntervalCensored", "lower": 2.0, "upper": 1.0, "error": 1.0,
+                "error_scale": {"Fixed": 1.0}}"#,
+            r#"{"type": "LogNormal", "value": -1.0, "geometric_error": 2.0}"#,
+            r#"{"type": "LogNormalLeftCensored", "detection_limit": 1.0, "geometric_error": 0.5}"#,
+            r#"{"type": "LogNormalIntervalCensored", "lower": 0.0, "upper": 1.0,
+                "geometric_error": 2.0}"#,
+            r#"{"type": "LogNormalRightCensored", "limit": -1.0, "geometric_error": 2.0}"#,
+            r#"{"type": "StudentT", "value": 1.0, "error": 1.0,
+                "degrees_of_freedom": {"Fixed": 0.0}}"#,
+            r#"{"type": "OutlierMixture", "value": 1.0, "error": 1.0, "outlier_error": 5.0,
+                "outlier_fraction": {"Fixed": 1.5}}"#,
+            r#"{"type": "NegativeBinomial", "count": 3, "dispersion": {"Fixed": -1.0}}"#,
+        ] {
+            assert!(serde_json::from_str::<Box<dyn PartialLikelihood>>(invalid).is_err());
+        }
+        let valid = r#"{"type": "StudentT", "value": 1.0, "error": 1.0,
+            "degrees_of_freedom": {"Inferred": 2}}"#;
+        assert!(serde_json::from_str::<Box<dyn PartialLikelihood>>(valid).is_ok());
+
+        let asymmetric = r#"{"values": [0.0, 0.0], "covariance": [[1.0, 0.8], [0.5, 1.0]]}"#;
+        assert!(serde_json::from_str::<CorrelatedObservationSet>(asymmetric).is_err());
+        let short = r#"{"values": [0.0, 0.0], "covariance": [[1.0]]}"#;
+        assert!(serde_json::from_str::<CorrelatedObservationSet>(short).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_constructors() {
+        let _ = Prediction::new(vec![0.0], vec![0.0, 1.0], 0.0);
+    }
+
+    #[test]
+    fn normalized_observation() {
+        let obs = Observation::new(1.0, 0.6);
+        let expected = Normal::new(1.0, 1.0).unwrap().ln_pdf(2.0);
+        assert_almost_eq!(obs.loglikelihood(&2.0, &0.8, &0.0), expected, 1e-12);
+
+        // broader errors are penalized rather than always preferred
+        let narrow = obs.loglikelihood(&1.0, &0.0, &1.0);
+        let wide = obs.loglikelihood(&1.0, &0.0, &100.0);
+        assert!(narrow > wide);
+    }
+
+    #[test]
+    fn unnormalized_observation() {
+        let obs = Observation::new_unnormalized(1.0, 1.0);
+        assert_eq!(obs.loglikelihood(&3.0, &0.0, &1.0), -2.0);
+    }
+
+    #[test]
+    fn observation_errors() {
+        let absolute = Observation::from_error(20.0, ObservationError::Absolute(2.0));
+        let relative = Observation::from_error(20.0, ObservationError::Relative(0.1));
+        let direct = Observation::new(20.0, 2.0);
+        assert_eq!(
+            absolute.loglikelihood(&18.0, &0.0, &0.0),
+            direct.loglikelihood(&18.0, &0.0, &0.0)
+        );
+        assert_eq!(
+            relative.loglikelihood(&18.0, &0.0, &0.0),
+            direct.loglikelihood(&18.0, &0.0, &0.0)
+        );
+        let nd = LeftCensoredObservation::from_error(10.0, ObservationError::Relative(0.3));
+        assert_eq!(
+            nd.loglikelihood(&8.0, &0.0, &0.0),
+            LeftCensoredObservation::new(10.0, 3.0).loglikelihood(&8.0, &0.0, &0.0)
+        );
+
+        let relative = ObservationError::Relative(0.1);
+        let pairs: [(Box<dyn PartialLikelihood>, Box<dyn PartialLikelihood>); 5] = [
+            (
+                Box::new(IntervalCensoredObservation::from_error(
+                    10.0, 20.0, relative,
+                )),
+                Box::new(IntervalCensoredObservation::new(10.0, 20.0, 2.0)),
+            ),
+            (
+                Box::new(RightCensoredObservation::from_error(20.0, relative)),
+                Box::new(RightCensoredObservation::new(20.0, 2.0)),
+            ),
+            (
+                Box::new(StudentTObservation::from_error(20.0, relative, 3.0)),
+                Box::new(StudentTObservation::new(20.0, 2.0, 3.0)),
+            ),
+            (
+                Box::new(CauchyObservation::from_error(20.0, relative)),
+                Box::new(CauchyObservation::new(20.0, 2.0)),
+            ),
+            (
+                Box::new(OutlierMixtureObservation::from_error(
+                    20.0, relative, 10.0, 0.1,
+                )),
+                Box::new(OutlierMixtureObservation::new(20.0, 2.0, 10.0, 0.1)),
+            ),
+        ];
+        for (from_error, direct) in pairs {
+            assert_eq!(
+                from_error.loglikelihood(&18.0, &0.5, &0.0),
+                direct.loglikelihood(&18.0, &0.5, &0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn inflated_errors() {
+        let proposal = Guess::new(&[0.0, 2.0]);
+        let inflated = Observation::new(1.0, 1.0).inflate_error(1);
+        assert_eq!(
+            inflated.loglikelihood_with_proposal(&2.0, &0.5, &0.0, &proposal),
+            Observation::new(1.0, 2.0).loglikelihood(&2.0, &0.5, &0.0)
+        );
+        let nd = LeftCensoredObservation::new(1.0, 1.0).inflate_error(1);
+        assert_eq!(
+            nd.loglikelihood_with_proposal(&2.0, &0.5, &0.0, &proposal),
+            LeftCensoredObservation::new(1.0, 2.0).loglikelihood(&2.0, &0.5, &0.0)
+        );
+        assert_eq!(
+            inflated.loglikelihood_with_proposal(&2.0, &0.5, &0.0, &Guess::new(&[0.0, -1.0])),
+            f64::NEG_INFINITY
+        );
+
+        // only observations sharing the factor are inflated
+        let set = ObservationSet::new(vec![
+            Box::new(Observation::new(1.0, 1.0).inflate_error(1)),
+            Box::new(Observation::new(1.0, 1.0)),
+        ]);
+        let ll = set.loglikelihood_with_proposal(
+            Prediction::new(vec![2.0, 2.0], vec![0.0; 2], 0.0),
+            &proposal,
+        );
+        let expected = Observation::new(1.0, 2.0).loglikelihood(&2.0, &0.0, &0.0)
+            + Observation::new(1.0, 1.0).loglikelihood(&2.0, &0.0, &0.0);
+        assert_almost_eq!(ll, expected, 1e-12);
+    }
+
+    #[test]
+    fn left_censored_observation() {
+        let nd = LeftCensoredObservation::new(10.0, 3.0);
+        let expected = Normal::new(8.0, 5.0).unwrap().cdf(10.0).ln();
+        assert_almost_eq!(nd.loglikelihood(&8.0, &4.0, &0.0), expected, 1e-12);
+        // prediction far below the limit is certain to be a nondetect
+        assert_almost_eq!(nd.loglikelihood(&-100.0, &1.0, &0.0), 0.0, 1e-12);
+        // and far above it stays finite
+        let far = nd.loglikelihood(&1000.0, &1.0, &0.0);
+        assert!(far.is_finite() && far < -1e4);
+
+        // no jump where the tail approximation takes over
+        for z in [-30.0, -35.0] {
+            assert_almost_eq!(ln_normal_cdf(z - 1e-9), ln_normal_cdf(z + 1e-9), 1e-6);
+        }
+        let exact = Normal::new(0.0, 1.0).unwrap().cdf(-32.0).ln();
+        assert_almost_eq!(ln_normal_cdf(-32.0), exact, 1e-9);
+    }
+
+    #[test]
+    fn interval_censored_observation() {
+        let obs = IntervalCensoredObservation::new(5.0, 10.0, 3.0);
+        let normal = Normal::new(7.0, 5.0).unwrap();
+        let expected = (normal.cdf(10.0) - normal.cdf(5.0)).ln();
+        assert_almost_eq!(obs.loglikelihood(&7.0, &4.0, &0.0), expected, 1e-12);
+        // upper tail, where 1 - cdf would lose precision
+        let tail = obs.loglikelihood(&-200.0, &1.0, &0.0);
+        let lower = LeftCensoredObservation::new(5.0, 3.0).loglikelihood(&-200.0, &1.0, &0.0);
+        assert!(tail.is_finite() && tail < lower);
+    }
+
+    #[test]
+    fn right_censored_observation() {
+        let obs = RightCensoredObservation::new(10.0, 3.0);
+        let expected = Normal::new(8.0, 5.0).unwrap().sf(10.0).ln();
+        assert_almost_eq!(obs.loglikelihood(&8.0, &4.0, &0.0), expected, 1e-12);
+        assert_almost_eq!(obs.loglikelihood(&1000.0, &1.0, &0.0), 0.0, 1e-12);
+    }
+
+    #[test]
+    fn lognormal_observation() {
+        let obs = LogNormalObservation::new(20.0, 2.0_f64.exp());
+        let expected = LogNormal::new(10.0_f64.ln(), 2.0).unwrap().ln_pdf(20.0);
+        assert_almost_eq!(obs.loglikelihood(&10.0, &0.0, &0.0), expected, 1e-12);
+        assert_eq!(obs.loglikelihood(&0.0, &0.0, &0.0), f64::NEG_INFINITY);
+        // a factor of two off is equally bad in either direction
+        let obs = LogNormalObservation::new(1.0, 1.5);
+        assert_almost_eq!(
+            obs.loglikelihood(&2.0, &0.0, &0.0),
+            obs.loglikelihood(&0.5, &0.0, &0.0),
+            1e-12
+        );
+    }
+
+    #[test]
+    fn lognormal_censored_observations() {
+        let dist = LogNormal::new(10.0_f64.ln(), 2.0).unwrap();
+        let gsd = 2.0_f64.exp();
+        let left = LogNormalLeftCensoredObservation::new(5.0, gsd);
+        assert_almost_eq!(
+            left.loglikelihood(&10.0, &0.0, &0.0),
+            dist.cdf(5.0).ln(),
+            1e-12
+        );
+        let interval = LogNormalIntervalCensoredObservation::new(5.0, 20.0, gsd);
+        assert_almost_eq!(
+            interval.loglikelihood(&10.0, &0.0, &0.0),
+            (dist.cdf(20.0) - dist.cdf(5.0)).ln(),
+            1e-12
+        );
+        let right = LogNormalRightCensoredObservation::new(20.0, gsd);
+        assert_almost_eq!(
+            right.loglikelihood(&10.0, &0.0, &0.0),
+            dist.sf(20.0).ln(),
+            1e-12
+        );
+    }
+
+    #[test]
+    fn student_t_observation() {
+        let obs = StudentTObservation::new(1.0, 0.6, 4.0);
+        let expected = StudentsT::new(1.0, 1.0, 4.0).unwrap().ln_pdf(3.0);
+        assert_almost_eq!(obs.loglikelihood(&3.0, &0.8, &0.0), expected, 1e-12);
+
+        // a far outlier costs much less than under the Gaussian
+        let gaussian = Observation::new(1.0, 1.0).loglikelihood(&20.0, &0.0, &0.0);
+        assert!(obs.loglikelihood(&20.0, &0.0, &0.8) > gaussian);
+    }
+
+    #[test]
+    fn student_t_inferred_dof() {
+        let obs = StudentTObservation::new_inferred(1.0, 1.0, 1);
+        let fixed = StudentTObservation::new(1.0, 1.0, 4.0);
+        let proposal = Guess::new(&[0.0, 4.0]);
+        assert_almost_eq!(
+            obs.loglikelihood_with_proposal(&3.0, &0.0, &0.0, &proposal),
+            fixed.loglikelihood(&3.0, &0.0, &0.0),
+            1e-12
+        );
+        let invalid = Guess::new(&[0.0, -1.0]);
+        assert_eq!(
+            obs.loglikelihood_with_proposal(&3.0, &0.0, &0.0, &invalid),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn student_t_inferred_dof_needs_proposal() {
+        let obs = StudentTObservation::new_inferred(1.0, 1.0, 1);
+        assert_eq!(obs.loglikelihood(&3.0, &0.0, &0.0), f64::NEG_INFINITY);
+        assert_eq!(
+            obs.loglikelihood_with_proposal(&3.0, &0.0, &0.0, &Guess::new(&[4.0])),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(obs.inferred_parameters(), vec![1]);
+    }
+
+    #[test]
+    fn cauchy_observation() {
+        let obs = CauchyObservation::new(1.0, 0.6);
+        let expected = Cauchy::new(1.0, 1.0).unwrap().ln_pdf(3.0);
+        assert_almost_eq!(obs.loglikelihood(&3.0, &0.8, &0.0), expected, 1e-12);
+        let student = StudentTObservation::new(1.0, 0.6, 1.0);
+        assert_almost_eq!(student.loglikelihood(&3.0, &0.8, &0.0), expected, 1e-12);
+    }
+
+    #[test]
+    fn outlier_mixture_observation() {
+        let obs = OutlierMixtureObservation::new(1.0, 1.0, 10.0, 0.1);
+        let good = Normal::new(1.0, 1.0).unwrap().pdf(2.0);
+        let bad = Normal::new(1.0, 101.0_f64.sqrt()).unwrap().pdf(2.0);
+        let expected = (0.9 * good + 0.1 * bad).ln();
+        assert_almost_eq!(obs.loglikelihood(&2.0, &0.0, &0.0), expected, 1e-12);
+
+        let proposal = Guess::new(&[]);
+        let p_near = obs
+            .outlier_probability(&2.0, &0.0, &0.0, &proposal)
+            .unwrap();
+        assert_almost_eq!(p_near, 0.1 * bad / (0.9 * good + 0.1 * bad), 1e-12);
+        let p_far = obs
+            .outlier_probability(&30.0, &0.0, &0.0, &proposal)
+            .unwrap();
+        assert!(p_far > 0.99);
+    }
+
+    #[test]
+    fn outlier_mixture_inferred_fraction() {
+        let obs = OutlierMixtureObservation::new_inferred(1.0, 1.0, 10.0, 0);
+        let fixed = OutlierMixtureObservation::new(1.0, 1.0, 10.0, 0.25);
+        assert_almost_eq!(
+            obs.loglikelihood_with_proposal(&2.0, &0.0, &0.0, &Guess::new(&[0.25])),
+            fixed.loglikelihood(&2.0, &0.0, &0.0),
+            1e-6
+        );
+        assert_eq!(
+            obs.loglikelihood_with_proposal(&2.0, &0.0, &0.0, &Guess::new(&[1.5])),
+            f64::NEG_INFINITY
+        );
+
+        let set = ObservationSet::new(vec![Box::new(Observation::new(1.0, 1.0)), Box::new(obs)]);
+        let prediction = Prediction::new(vec![1.0, 30.0], vec![0.0; 2], 0.0);
+        let probabilities = set.outlier_probabilities(prediction, &Guess::new(&[0.25]));
+        assert_eq!(probabilities[0], None);
+        assert!(probabilities[1].unwrap() > 0.99);
+    }
+
+    #[test]
+    fn correlated_observation_set() {
+        // diagonal covariance reduces to independent observations
+        let independent = ObservationSet::new(vec![
+            Box::new(Observation::new(1.0, 1.0)),
+            Box::new(Observation::new(2.0, 2.0)),
+        ]);
+        let correlated = CorrelatedObservationSet::new(
+            vec![1.0, 2.0],
+            Covariance::Grouped {
+                errors: vec![1.0, 2.0],
+                groups: vec![0, 1],
+                correlation: 0.5,
+            },
+        );
+        let expected =
+            independent.loglikelihood(Prediction::new(vec![0.0, 0.0], vec![0.5; 2], 1.0));
+        let ll = correlated.loglikelihood(Prediction::new(vec![0.0, 0.0], vec![0.5; 2], 1.0));
+        assert_almost_eq!(ll, expected, 1e-12);
+
+        let full = CorrelatedObservationSet::new(
+            vec![0.0, 0.0],
+            Covariance::Full(vec![vec![1.0, 0.8], vec![0.8, 1.0]]),
+        );
+        // errors moving together are more likely than opposite errors
+        let together = full.loglikelihood(Prediction::new(vec![1.0, 1.0], vec![0.0; 2], 0.0));
+        let opposite = full.loglikelihood(Prediction::new(vec![1.0, -1.0], vec![0.0; 2], 0.0));
+        assert!(together > opposite);
+
+        let short = full.loglikelihood(Prediction::new(vec![1.0], vec![0.0], 0.0));
+        assert_eq!(short, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "covariance must be symmetric")]
+    fn asymmetric_covariance() {
+        let _ = CorrelatedObservationSet::new(
+            vec![0.0, 0.0],
+            Covariance::Full(vec![vec![1.0, 0.8], vec![0.5, 1.0]]),
+        );
+    }
+
+    #[test]
+    fn structured_covariance() {
+        let grouped = Covariance::Grouped {
+            errors: vec![1.0, 2.0, 3.0],
+            groups: vec![0, 0, 1],
+            correlation: 0.5,
+        };
+        assert_eq!(
+            grouped.matrix(),
+            vec![
+                vec![1.0, 1.0, 0.0],
+                vec![1.0, 4.0, 0.0],
+                vec![0.0, 0.0, 9.0]
+            ]
+        );
+        let decay = Covariance::ExponentialDecay {
+            errors: vec![1.0, 1.0],
+            coordinates: vec![vec![0.0, 0.0], vec![3.0, 4.0]],
+            length_scale: 5.0,
+        };
+        assert_almost_eq!(decay.matrix()[0][1], (-1.0_f64).exp(), 1e-12);
+    }
+
+    #[test]
+    fn poisson_observation() {
+        let obs = PoissonObservation::new(3);
+        let expected = Poisson::new(2.5).unwrap().ln_pmf(3);
+        assert_almost_eq!(obs.loglikelihood(&2.5, &0.0, &0.0), expected, 1e-12);
+        assert_eq!(
+            PoissonObservation::new(0).loglikelihood(&0.0, &0.0, &0.0),
+            0.0
+        );
+        assert_eq!(obs.loglikelihood(&0.0, &0.0, &0.0), f64::NEG_INFINITY);
+        assert_eq!(obs.loglikelihood(&-1.0, &0.0, &0.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn negative_binomial_observation() {
+        // statrs parameterizes by success probability p = r / (r + mean)
+        let obs = NegativeBinomialObservation::new(3, 2.0);
+        let expected = NegativeBinomial::new(2.0, 2.0 / 4.5).unwrap().ln_pmf(3);
+        assert_almost_eq!(obs.loglikelihood(&2.5, &0.0, &0.0), expected, 1e-12);
+
+        // large dispersion approaches Poisson
+        let near_poisson = NegativeBinomialObservation::new(3, 1e8);
+        assert_almost_eq!(
+            near_poisson.loglikelihood(&2.5, &0.0, &0.0),
+            PoissonObservation::new(3).loglikelihood(&2.5, &0.0, &0.0),
+            1e-6
+        );
+
+        let inferred = NegativeBinomialObservation::new_inferred(3, 0);
+        assert_almost_eq!(
+            inferred.loglikelihood_with_proposal(&2.5, &0.0, &0.0, &Guess::new(&[2.0])),
+            expected,
+            1e-12
+        );
+        assert_eq!(
+            inferred.loglikelihood_with_proposal(&2.5, &0.0, &0.0, &Guess::new(&[-2.0])),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn mixed_observation_set() {
+        let set = ObservationSet::new(vec![
+            Box::new(Observation::new(3.0, 1.0)),
+            Box::new(LeftCensoredObservation::new(1.0, 1.0)),
+            Box::new(IntervalCensoredObservation::new(1.0, 2.0, 1.0)),
+            Box::new(RightCensoredObservation::new(10.0, 1.0)),
+        ]);
+        let prediction = Prediction::new(vec![3.0, 0.0, 1.5, 12.0], vec![0.0; 4], 0.0);
+        let expected = Observation::new(3.0, 1.0).loglikelihood(&3.0, &0.0, &0.0)
+            + LeftCensoredObservation::new(1.0, 1.0).loglikelihood(&0.0, &0.0, &0.0)
+            + IntervalCensoredObservation::new(1.0, 2.0, 1.0).loglikelihood(&1.5, &0.0, &0.0)
+            + RightCensoredObservation::new(10.0, 1.0).loglikelihood(&12.0, &0.0, &0.0);
+        let ll = set.loglikelihood(prediction);
+        assert_almost_eq!(ll, expected, 1e-12);
+    }
+
+    #[test]
+    fn predictive_samples() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let set = ObservationSet::new(vec![
+            Box::new(Observation::new(3.0, 1.0).inflate_error(0)),
+            Box::new(LogNormalObservation::new(2.0, 1.5)),
+            Box::new(PoissonObservation::new(4)),
+        ]);
+        let proposal = Guess::new(&[2.0]);
+        let mut rng = StdRng::seed_from_u64(5);
+        let draws: Vec<Vec<f64>> = (0..4000)
+            .map(|_| {
+                let prediction = Prediction::new(vec![3.0, 2.0, 4.0], vec![0.0; 3], 0.0);
+                set.predictive_samples(&prediction, &proposal, &mut rng)
+            })
+            .collect();
+        let column = |i: usize| draws.iter().map(|d| d[i]).collect::<Vec<f64>>();
+        let mean = |x: &[f64]| x.iter().sum::<f64>() / x.len() as f64;
+        let variance = |x: &[f64]| {
+            let m = mean(x);
+            x.iter().map(|v| (v - m).powi(2)).sum::<f64>() / x.len() as f64
+        };
+
+        // the inflated error is twice the reported one
+        let gaussian = column(0);
+        assert_almost_eq!(mean(&gaussian), 3.0, 0.1);
+        assert_almost_eq!(variance(&gaussian), 4.0, 0.3);
+        // log-normal draws are positive with the geometric spread
+        let log_normal = column(1);
+        assert!(log_normal.iter().all(|v| *v > 0.0));
+        let logs: Vec<f64> = log_normal.iter().map(|v| v.ln()).collect();
+        assert_almost_eq!(variance(&logs), 1.5_f64.ln().powi(2), 0.02);
+        // counts are whole numbers with the Poisson variance
+        let counts = column(2);
+        assert!(counts.iter().all(|v| v.fract() == 0.0));
+        assert_almost_eq!(variance(&counts), 4.0, 0.4);
     }
 }
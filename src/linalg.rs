@@ -0,0 +1,70 @@
+/// Lower-triangular Cholesky factor of a symmetric positive definite matrix,
+/// or `None` if the matrix is not positive definite.
+pub(crate) fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                let d = matrix[i][i] - sum;
+                if d <= 0.0 || !d.is_finite() {
+                    return None;
+                }
+                lower[i][j] = d.sqrt();
+            } else {
+                lower[i][j] = (matrix[i][j] - sum) / lower[j][j];
+            }
+        }
+    }
+    Some(lower)
+}
+
+/// Solve `L x = b` for lower-triangular `L`.
+pub(crate) fn forward_substitute(lower: &[Vec<f64>], b: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; b.len()];
+    for i in 0..b.len() {
+        let sum: f64 = (0..i).map(|k| lower[i][k] * x[k]).sum();
+        x[i] = (b[i] - sum) / lower[i][i];
+    }
+    x
+}
+
+/// Log-density of a zero-mean multivariate normal at `residual`, given the
+/// Cholesky factor of its covariance.
+pub(crate) fn mvn_logdensity(lower: &[Vec<f64>], residual: &[f64]) -> f64 {
+    let y = forward_substitute(lower, residual);
+    let mahalanobis: f64 = y.iter().map(|v| v.powi(2)).sum();
+    let log_det: f64 = 2.0
+        * lower
+            .iter()
+            .enumerate()
+            .map(|(i, row)| row[i].ln())
+            .sum::<f64>();
+    -0.5 * (mahalanobis + log_det + residual.len() as f64 * (2.0 * std::f64::consts::PI).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statrs::assert_almost_eq;
+
+    #[test]
+    fn cholesky_factor() {
+        let matrix = vec![vec![4.0, 2.0], vec![2.0, 3.0]];
+        let lower = cholesky(&matrix).unwrap();
+        assert_almost_eq!(lower[0][0], 2.0, 1e-12);
+        assert_almost_eq!(lower[1][0], 1.0, 1e-12);
+        assert_almost_eq!(lower[1][1], 2.0_f64.sqrt(), 1e-12);
+        assert!(cholesky(&[vec![1.0, 2.0], vec![2.0, 1.0]]).is_none());
+    }
+
+    #[test]
+    fn diagonal_density() {
+        let lower = cholesky(&[vec![4.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        let expected = -0.5 * (1.0 + 1.0)
+            - 0.5 * (2.0 * std::f64::consts::PI * 4.0).ln()
+            - 0.5 * (2.0 * std::f64::consts::PI).ln();
+        assert_almost_eq!(mvn_logdensity(&lower, &[2.0, 1.0]), expected, 1e-12);
+    }
+}
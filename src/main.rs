@@ -0,0 +1,24 @@
+use letsbayes::config::RunConfig;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("usage: {} <config.json>", args[0]);
+        std::process::exit(2);
+    }
+    let config = RunConfig::from_file(&args[1]).unwrap_or_else(|e| {
+        eprintln!("error: could not load {}: {}", args[1], e);
+        std::process::exit(1);
+    });
+    match config.run() {
+        Ok(posterior) => println!(
+            "Sampled {} parameters; posterior written to {}",
+            posterior.parameter_names().len(),
+            config.posterior_path().display()
+        ),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use emcee::Guess;
+use serde::{Deserialize, Serialize};
 
 pub struct Prediction {
     pub observables: Vec<f64>,
@@ -21,23 +22,37 @@ pub trait Model {
     fn predict(&self, proposal: &Guess) -> Prediction;
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct InfluenceFunction {
     weights: Vec<Vec<f64>>,
     relative_error: f64,
 }
 
 impl InfluenceFunction {
+    /// `weights[i][j]` is the influence of source `i` on observation `j`.
     pub fn new(weights: Vec<Vec<f64>>, relative_error: f64) -> Self {
+        assert!(
+            weights.iter().all(|w| w.len() == weights[0].len()),
+            "every source needs one weight per observation"
+        );
         Self {
             weights,
             relative_error,
         }
     }
+
+    fn n_observations(&self) -> usize {
+        self.weights.first().map_or(0, |w| w.len())
+    }
+
+    pub fn weights(&self) -> &[Vec<f64>] {
+        &self.weights
+    }
 }
 
 impl Model for InfluenceFunction {
     fn predict(&self, proposal: &Guess) -> Prediction {
-        let mut vals = vec![0.0; self.weights.len()];
+        let mut vals = vec![0.0; self.n_observations()];
         for (p, ws) in proposal.values.iter().zip(&self.weights) {
             vals = vals
                 .iter()
@@ -50,23 +65,33 @@ impl Model for InfluenceFunction {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct InfluenceFunctionLog {
     weights: Vec<Vec<f64>>,
     relative_error: f64,
 }
 
 impl InfluenceFunctionLog {
+    /// `weights[i][j]` is the influence of source `i` on observation `j`.
     pub fn new(weights: Vec<Vec<f64>>, relative_error: f64) -> Self {
+        assert!(
+            weights.iter().all(|w| w.len() == weights[0].len()),
+            "every source needs one weight per observation"
+        );
         Self {
             weights,
             relative_error,
         }
     }
+
+    fn n_observations(&self) -> usize {
+        self.weights.first().map_or(0, |w| w.len())
+    }
 }
 
 impl Model for InfluenceFunctionLog {
     fn predict(&self, proposal: &Guess) -> Prediction {
-        let mut vals = vec![0.0; self.weights.len()];
+        let mut vals = vec![0.0; self.n_observations()];
         for (p, ws) in proposal.values.iter().zip(&self.weights) {
             vals = vals
                 .iter()
@@ -94,4 +119,14 @@ mod tests {
     fn test_invalid_constructors() {
         let _ = Prediction::new(vec![0.0], vec![0.0, 1.0], 0.0);
     }
+
+    #[test]
+    fn influence_function_predicts_every_observation() {
+        let model = InfluenceFunction::new(vec![vec![1.0, 2.0, 3.0], vec![0.5, 0.0, 1.0]], 0.1);
+        let prediction = model.predict(&Guess::new(&[2.0, 4.0]));
+        assert_eq!(prediction.observables, vec![4.0, 4.0, 10.0]);
+        assert_eq!(prediction.errors.len(), 3);
+        let log = InfluenceFunctionLog::new(vec![vec![1.0, 2.0, 3.0], vec![0.5, 0.0, 1.0]], 0.1);
+        assert_eq!(log.predict(&Guess::new(&[1.0, 0.0])).observables.len(), 3);
+    }
 }
@@ -26,21 +26,43 @@ impl Posterior {
         let dimension = parameter_names.len();
         Self {
             parameter_names,
-            samples: samples
-                .into_iter()
-                .map(|x| SaveGuess::from_guess(x))
-                .collect(),
+            samples: samples.into_iter().map(SaveGuess::from_guess).collect(),
             dimension,
         }
     }
+    pub fn parameter_names(&self) -> &[String] {
+        &self.parameter_names
+    }
+
+    pub fn samples(&self) -> Vec<Guess> {
+        self.samples.iter().map(|x| Guess::new(&x.values)).collect()
+    }
+
+    /// Samples after discarding the first `skip` and keeping every
+    /// `thinning`-th, as in `to_csv`.
+    pub fn thinned_samples(&self, skip: usize, thinning: usize) -> Vec<Guess> {
+        self.samples
+            .iter()
+            .skip(skip)
+            .step_by(thinning)
+            .map(|x| Guess::new(&x.values))
+            .collect()
+    }
+
+    /// All samples of the named parameter.
+    pub fn parameter_samples(&self, name: &str) -> Option<Vec<f32>> {
+        let index = self.parameter_names.iter().position(|n| n == name)?;
+        Some(self.samples.iter().map(|x| x.values[index]).collect())
+    }
+
     pub fn to_csv(&self, filename: &str, skip: usize, thinning: usize) -> std::io::Result<()> {
         let mut string_out = "".to_string();
         for parameter_name in self.parameter_names.iter() {
-            string_out.push_str(&parameter_name);
+            string_out.push_str(parameter_name);
             string_out.push(',')
         }
         string_out.pop();
-        string_out.push_str("\n");
+        string_out.push('\n');
         for sample in self.samples.iter().skip(skip).step_by(thinning) {
             let mut sample_string: String = sample
                 .values
@@ -48,7 +70,7 @@ impl Posterior {
                 .map(|x| x.to_string())
                 .collect::<Vec<String>>()
                 .join(",");
-            sample_string.push_str("\n");
+            sample_string.push('\n');
             string_out.push_str(&sample_string)
         }
         std::fs::write(filename, string_out)?;
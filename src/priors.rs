@@ -1,18 +1,535 @@
+use std::any::Any;
+
 use emcee::{Guess, Prob};
-use statrs::distribution::Continuous;
-use statrs::statistics::Distribution;
+
+use crate::linalg::{cholesky, mvn_logdensity};
+use crate::posterior::Posterior;
+use rand::{Rng, RngCore};
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use statrs::distribution::{
+    Beta, Cauchy, Chi, ChiSquared, Continuous, ContinuousCDF, Erlang, Exp, FisherSnedecor, Gamma,
+    InverseGamma, Laplace, LogNormal, Normal, Pareto, StudentsT, Triangular, Uniform, Weibull,
+};
+use statrs::function::gamma::ln_gamma;
+use statrs::statistics::{Distribution, Max, Median, Min, Mode};
 
 pub trait PartialPrior {
     fn logprobability(&self, proposed: &f32) -> f64;
 
     fn initial_guess(&self) -> f64;
+
+    /// Random draw used to disperse the initial walkers. Defaults to a
+    /// small perturbation of the initial guess, as in the default
+    /// `Prior::initial_ensemble`.
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        perturb(self.initial_guess(), rng)
+    }
+
+    /// Serializable description of this prior, if it is a built-in one.
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        None
+    }
+}
+
+/// `value` plus Gaussian noise of relative size 1e-5, large enough to survive
+/// the conversion to `f32`.
+fn perturb(value: f64, rng: &mut dyn RngCore) -> f64 {
+    let noise: f64 = rand::distributions::Distribution::sample(&Normal::standard(), rng);
+    value + 1e-5 * value.abs().max(1.0) * noise
 }
+
 pub trait Prior {
     fn logprobability(&self, proposal: &Guess) -> f64;
 
     fn initial_guess(&self) -> Guess;
+
+    /// Starting positions for `n_walkers` walkers. Defaults to small
+    /// perturbations around the initial guess drawn from `rng`, so seeded
+    /// runs are reproducible.
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        let guess = self.initial_guess();
+        (0..n_walkers)
+            .map(|_| {
+                Guess::new(
+                    &guess
+                        .values
+                        .iter()
+                        .map(|x| perturb(*x as f64, rng) as f32)
+                        .collect::<Vec<f32>>(),
+                )
+            })
+            .collect()
+    }
+
+    /// Serializable description of this prior, if it is a built-in one.
+    fn spec(&self) -> Option<PriorSpec> {
+        None
+    }
+}
+
+/// Tagged description of a distribution for `IndependentPrior` and
+/// `TruncatedPrior`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DistributionSpec {
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    Cauchy {
+        location: f64,
+        scale: f64,
+    },
+    Gamma {
+        shape: f64,
+        rate: f64,
+    },
+    Exponential {
+        rate: f64,
+    },
+    StudentsT {
+        location: f64,
+        scale: f64,
+        freedom: f64,
+    },
+    Beta {
+        shape_a: f64,
+        shape_b: f64,
+    },
+    Laplace {
+        location: f64,
+        scale: f64,
+    },
+    LogNormal {
+        location: f64,
+        scale: f64,
+    },
+    Weibull {
+        shape: f64,
+        scale: f64,
+    },
+    Pareto {
+        scale: f64,
+        shape: f64,
+    },
+    ChiSquared {
+        freedom: f64,
+    },
+    Chi {
+        freedom: f64,
+    },
+    Erlang {
+        shape: u64,
+        rate: f64,
+    },
+    InverseGamma {
+        shape: f64,
+        rate: f64,
+    },
+    Triangular {
+        min: f64,
+        max: f64,
+        mode: f64,
+    },
+    FisherSnedecor {
+        freedom_1: f64,
+        freedom_2: f64,
+    },
+}
+
+/// Description of the statrs continuous distributions as a `DistributionSpec`.
+/// Priors over other distributions cannot be serialized.
+fn describe(distribution: &dyn Any) -> Option<DistributionSpec> {
+    if let Some(d) = distribution.downcast_ref::<Normal>() {
+        return Some(DistributionSpec::Normal {
+            mean: d.mean()?,
+            std_dev: d.std_dev()?,
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Uniform>() {
+        return Some(DistributionSpec::Uniform {
+            min: d.min(),
+            max: d.max(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Cauchy>() {
+        return Some(DistributionSpec::Cauchy {
+            location: d.location(),
+            scale: d.scale(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Gamma>() {
+        return Some(DistributionSpec::Gamma {
+            shape: d.shape(),
+            rate: d.rate(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Exp>() {
+        return Some(DistributionSpec::Exponential { rate: d.rate() });
+    }
+    if let Some(d) = distribution.downcast_ref::<StudentsT>() {
+        return Some(DistributionSpec::StudentsT {
+            location: d.location(),
+            scale: d.scale(),
+            freedom: d.freedom(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Beta>() {
+        return Some(DistributionSpec::Beta {
+            shape_a: d.shape_a(),
+            shape_b: d.shape_b(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Laplace>() {
+        return Some(DistributionSpec::Laplace {
+            location: d.location(),
+            scale: d.scale(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<LogNormal>() {
+        // statrs has no accessors here; the median is e^μ and the mode e^(μ - σ²)
+        let location = d.median().ln();
+        return Some(DistributionSpec::LogNormal {
+            location,
+            scale: (location - d.mode()?.ln()).max(0.0).sqrt(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Weibull>() {
+        return Some(DistributionSpec::Weibull {
+            shape: d.shape(),
+            scale: d.scale(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Pareto>() {
+        return Some(DistributionSpec::Pareto {
+            scale: d.scale(),
+            shape: d.shape(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<ChiSquared>() {
+        return Some(DistributionSpec::ChiSquared {
+            freedom: d.freedom(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Chi>() {
+        return Some(DistributionSpec::Chi {
+            freedom: d.freedom(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Erlang>() {
+        return Some(DistributionSpec::Erlang {
+            shape: d.shape(),
+            rate: d.rate(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<InverseGamma>() {
+        return Some(DistributionSpec::InverseGamma {
+            shape: d.shape(),
+            rate: d.rate(),
+        });
+    }
+    if let Some(d) = distribution.downcast_ref::<Triangular>() {
+        return Some(DistributionSpec::Triangular {
+            min: d.min(),
+            max: d.max(),
+            mode: d.mode()?,
+        });
+    }
+    distribution
+        .downcast_ref::<FisherSnedecor>()
+        .map(|d| DistributionSpec::FisherSnedecor {
+            freedom_1: d.freedom_1(),
+            freedom_2: d.freedom_2(),
+        })
+}
+
+/// Evaluates `body` with `$d` bound to the `Result` of building the statrs
+/// distribution that `$spec` describes.
+macro_rules! with_spec_distribution {
+    ($spec:expr, |$d:ident| $body:expr) => {
+        match $spec {
+            DistributionSpec::Normal { mean, std_dev } => {
+                let $d = Normal::new(mean, std_dev);
+                $body
+            }
+            DistributionSpec::Uniform { min, max } => {
+                let $d = Uniform::new(min, max);
+                $body
+            }
+            DistributionSpec::Cauchy { location, scale } => {
+                let $d = Cauchy::new(location, scale);
+                $body
+            }
+            DistributionSpec::Gamma { shape, rate } => {
+                let $d = Gamma::new(shape, rate);
+                $body
+            }
+            DistributionSpec::Exponential { rate } => {
+                let $d = Exp::new(rate);
+                $body
+            }
+            DistributionSpec::StudentsT {
+                location,
+                scale,
+                freedom,
+            } => {
+                let $d = StudentsT::new(location, scale, freedom);
+                $body
+            }
+            DistributionSpec::Beta { shape_a, shape_b } => {
+                let $d = Beta::new(shape_a, shape_b);
+                $body
+            }
+            DistributionSpec::Laplace { location, scale } => {
+                let $d = Laplace::new(location, scale);
+                $body
+            }
+            DistributionSpec::LogNormal { location, scale } => {
+                let $d = LogNormal::new(location, scale);
+                $body
+            }
+            DistributionSpec::Weibull { shape, scale } => {
+                let $d = Weibull::new(shape, scale);
+                $body
+            }
+            DistributionSpec::Pareto { scale, shape } => {
+                let $d = Pareto::new(scale, shape);
+                $body
+            }
+            DistributionSpec::ChiSquared { freedom } => {
+                let $d = ChiSquared::new(freedom);
+                $body
+            }
+            DistributionSpec::Chi { freedom } => {
+                let $d = Chi::new(freedom);
+                $body
+            }
+            DistributionSpec::Erlang { shape, rate } => {
+                let $d = Erlang::new(shape, rate);
+                $body
+            }
+            DistributionSpec::InverseGamma { shape, rate } => {
+                let $d = InverseGamma::new(shape, rate);
+                $body
+            }
+            DistributionSpec::Triangular { min, max, mode } => {
+                let $d = Triangular::new(min, max, mode);
+                $body
+            }
+            DistributionSpec::FisherSnedecor {
+                freedom_1,
+                freedom_2,
+            } => {
+                let $d = FisherSnedecor::new(freedom_1, freedom_2);
+                $body
+            }
+        }
+    };
+}
+
+impl DistributionSpec {
+    /// An `IndependentPrior`, or a `TruncatedPrior` if `bounds` are given.
+    fn prior(self, bounds: Option<(f64, f64)>) -> Result<Box<dyn PartialPrior>, PriorError> {
+        with_spec_distribution!(self, |d| boxed(d, bounds))
+    }
+
+    /// The median of the described distribution, `None` if it is invalid.
+    fn median(self) -> Option<f64> {
+        with_spec_distribution!(self, |d| d.ok().map(|d| quantile(&d, 0.5)))
+    }
+}
+
+fn boxed<T, E>(
+    distribution: Result<T, E>,
+    bounds: Option<(f64, f64)>,
+) -> Result<Box<dyn PartialPrior>, PriorError>
+where
+    T: Distribution<f64>
+        + Continuous<f64, f64>
+        + ContinuousCDF<f64, f64>
+        + rand::distributions::Distribution<f64>
+        + 'static,
+    E: std::fmt::Display,
+{
+    let distribution = distribution.map_err(|e| invalid(&e.to_string()))?;
+    Ok(match bounds {
+        None => Box::new(IndependentPrior { distribution }),
+        Some((lower, upper)) => Box::new(TruncatedPrior::try_new(distribution, lower, upper)?),
+    })
+}
+
+/// Tagged description of the built-in single-parameter priors. Infinite
+/// truncation bounds are left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PartialPriorSpec {
+    Independent {
+        distribution: DistributionSpec,
+    },
+    Truncated {
+        distribution: DistributionSpec,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lower: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        upper: Option<f64>,
+    },
+    LogUniform {
+        lower: f64,
+        upper: f64,
+    },
+    LogNormal {
+        median: f64,
+        geometric_sd: f64,
+    },
+    SpikeAndSlab {
+        inclusion_probability: f64,
+        spike_scale: f64,
+        slab: Box<PartialPriorSpec>,
+    },
+}
+
+impl PartialPriorSpec {
+    /// Fails for invalid parameters.
+    pub fn build(self) -> Result<Box<dyn PartialPrior>, PriorError> {
+        Ok(match self {
+            PartialPriorSpec::Independent { distribution } => distribution.prior(None)?,
+            PartialPriorSpec::Truncated {
+                distribution,
+                lower,
+                upper,
+            } => distribution.prior(Some((
+                lower.unwrap_or(f64::NEG_INFINITY),
+                upper.unwrap_or(f64::INFINITY),
+            )))?,
+            PartialPriorSpec::LogUniform { lower, upper } => {
+                Box::new(LogUniformPrior::try_new(lower, upper)?)
+            }
+            PartialPriorSpec::LogNormal {
+                median,
+                geometric_sd,
+            } => Box::new(LogNormalPrior::try_new(median, geometric_sd)?),
+            PartialPriorSpec::SpikeAndSlab {
+                inclusion_probability,
+                spike_scale,
+                slab,
+            } => Box::new(SpikeAndSlabPrior::try_new(
+                inclusion_probability,
+                spike_scale,
+                slab.build()?,
+            )?),
+        })
+    }
+}
+
+/// Tagged description of the built-in priors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PriorSpec {
+    Basic {
+        partial_priors: Vec<PartialPriorSpec>,
+    },
+    MultivariateNormal {
+        mean: Vec<f64>,
+        covariance: Vec<Vec<f64>>,
+    },
+    KernelDensity {
+        points: Vec<Vec<f64>>,
+        bandwidths: Vec<f64>,
+    },
+    Dirichlet {
+        alpha: Vec<f64>,
+    },
+    Hierarchical {
+        population: Population,
+        location_prior: PartialPriorSpec,
+        scale_prior: PartialPriorSpec,
+        n_members: usize,
+    },
+    Composite {
+        components: Vec<PriorSpec>,
+    },
+}
+
+impl PriorSpec {
+    /// See `PartialPriorSpec::build`.
+    pub fn build(self) -> Result<Box<dyn Prior>, PriorError> {
+        Ok(match self {
+            PriorSpec::Basic { .. } => Box::new(BasicPrior::try_from(self)?),
+            PriorSpec::MultivariateNormal { .. } => {
+                Box::new(MultivariateNormalPrior::try_from(self)?)
+            }
+            PriorSpec::KernelDensity { .. } => Box::new(KernelDensityPrior::try_from(self)?),
+            PriorSpec::Dirichlet { .. } => Box::new(DirichletPrior::try_from(self)?),
+            PriorSpec::Hierarchical { .. } => Box::new(HierarchicalPrior::try_from(self)?),
+            PriorSpec::Composite { .. } => Box::new(CompositePrior::try_from(self)?),
+        })
+    }
+}
+
+fn unexpected(expected: &str, spec: &PriorSpec) -> PriorError {
+    PriorError::Invalid(format!("expected a {} prior, found {:?}", expected, spec))
+}
+
+impl Serialize for dyn PartialPrior {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spec()
+            .ok_or_else(|| S::Error::custom("prior has no serializable description"))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn PartialPrior> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PartialPriorSpec::deserialize(deserializer)?
+            .build()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for dyn Prior {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spec()
+            .ok_or_else(|| S::Error::custom("prior has no serializable description"))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Prior> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PriorSpec::deserialize(deserializer)?
+            .build()
+            .map_err(D::Error::custom)
+    }
 }
 
+/// The built-in priors serialize as their `PriorSpec`.
+macro_rules! serialize_as_spec {
+    ($($prior:ty),*) => {
+        $(
+            impl Serialize for $prior {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    (self as &dyn Prior).serialize(serializer)
+                }
+            }
+        )*
+    };
+}
+
+serialize_as_spec!(
+    BasicPrior,
+    MultivariateNormalPrior,
+    KernelDensityPrior,
+    DirichletPrior,
+    HierarchicalPrior,
+    CompositePrior
+);
+
 #[derive(Debug, Clone, Copy)]
 pub struct IndependentPrior<T: Distribution<f64> + Continuous<f64, f64>> {
     pub distribution: T,
@@ -20,18 +537,572 @@ pub struct IndependentPrior<T: Distribution<f64> + Continuous<f64, f64>> {
 
 impl<T> PartialPrior for IndependentPrior<T>
 where
-    T: Distribution<f64> + Continuous<f64, f64>,
+    T: Distribution<f64> + Continuous<f64, f64> + 'static,
+{
+    fn logprobability(&self, proposed: &f32) -> f64 {
+        let p: f64 = *proposed as f64;
+        self.distribution.ln_pdf(p)
+    }
+
+    /// The median for statrs distributions, which exists even where the mean
+    /// does not (e.g. Cauchy), and the mean otherwise.
+    fn initial_guess(&self) -> f64 {
+        describe(&self.distribution)
+            .and_then(DistributionSpec::median)
+            .unwrap_or_else(|| self.distribution.mean().expect("Distribution has no mean?"))
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rand::distributions::Distribution::sample(&self.distribution, rng)
+    }
+
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        Some(PartialPriorSpec::Independent {
+            distribution: describe(&self.distribution)?,
+        })
+    }
+}
+
+/// Quantile by bisection on the CDF. statrs' default `inverse_cdf` stops
+/// after a fixed, small number of steps, which is too coarse for wide priors.
+fn quantile<T: ContinuousCDF<f64, f64>>(distribution: &T, p: f64) -> f64 {
+    let (mut low, mut high) = (-1.0_f64, 1.0_f64);
+    while distribution.cdf(low) > p && low.is_finite() {
+        low *= 2.0;
+    }
+    while distribution.cdf(high) < p && high.is_finite() {
+        high *= 2.0;
+    }
+    loop {
+        let mid = 0.5 * (low + high);
+        if mid <= low || mid >= high {
+            return mid;
+        }
+        if distribution.cdf(mid) >= p {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+}
+
+impl<T> IndependentPrior<T>
+where
+    T: Distribution<f64> + Continuous<f64, f64> + ContinuousCDF<f64, f64>,
+{
+    /// Restrict the distribution to `[lower, upper]`, e.g. a non-negative
+    /// `Normal(50, 30)` via `truncated(0.0, f64::INFINITY)`.
+    pub fn truncated(self, lower: f64, upper: f64) -> TruncatedPrior<T> {
+        TruncatedPrior::new(self.distribution, lower, upper)
+    }
+}
+
+/// Any continuous distribution renormalized to the interval `[lower, upper]`.
+/// Either bound may be infinite.
+#[derive(Debug, Clone, Copy)]
+pub struct TruncatedPrior<T: Continuous<f64, f64> + ContinuousCDF<f64, f64>> {
+    distribution: T,
+    lower: f64,
+    upper: f64,
+    cdf_lower: f64,
+    cdf_upper: f64,
+}
+
+impl<T> TruncatedPrior<T>
+where
+    T: Continuous<f64, f64> + ContinuousCDF<f64, f64>,
+{
+    pub fn new(distribution: T, lower: f64, upper: f64) -> Self {
+        Self::try_new(distribution, lower, upper).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid bounds.
+    pub fn try_new(distribution: T, lower: f64, upper: f64) -> Result<Self, PriorError> {
+        require(
+            lower < upper,
+            "truncation lower bound must be below upper bound",
+        )?;
+        let cdf_lower = if lower.is_finite() {
+            distribution.cdf(lower)
+        } else {
+            0.0
+        };
+        let cdf_upper = if upper.is_finite() {
+            distribution.cdf(upper)
+        } else {
+            1.0
+        };
+        require(
+            cdf_upper > cdf_lower,
+            "distribution has no mass between truncation bounds",
+        )?;
+        Ok(Self {
+            distribution,
+            lower,
+            upper,
+            cdf_lower,
+            cdf_upper,
+        })
+    }
+}
+
+impl<T> PartialPrior for TruncatedPrior<T>
+where
+    T: Continuous<f64, f64> + ContinuousCDF<f64, f64> + 'static,
 {
     fn logprobability(&self, proposed: &f32) -> f64 {
         let p: f64 = *proposed as f64;
+        if p < self.lower || p > self.upper {
+            return f64::NEG_INFINITY;
+        }
+        self.distribution.ln_pdf(p) - (self.cdf_upper - self.cdf_lower).ln()
+    }
+
+    /// Median of the truncated distribution, which always lies within the
+    /// bounds.
+    fn initial_guess(&self) -> f64 {
+        quantile(&self.distribution, 0.5 * (self.cdf_lower + self.cdf_upper))
+            .clamp(self.lower, self.upper)
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        let u = rng.gen_range(self.cdf_lower..self.cdf_upper);
+        quantile(&self.distribution, u).clamp(self.lower, self.upper)
+    }
+
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        Some(PartialPriorSpec::Truncated {
+            distribution: describe(&self.distribution)?,
+            lower: Some(self.lower).filter(|x| x.is_finite()),
+            upper: Some(self.upper).filter(|x| x.is_finite()),
+        })
+    }
+}
+
+/// Log-uniform (Jeffreys) prior on `[lower, upper]` for positive scale
+/// parameters spanning orders of magnitude.
+#[derive(Debug, Clone, Copy)]
+pub struct LogUniformPrior {
+    lower: f64,
+    upper: f64,
+}
+
+impl LogUniformPrior {
+    pub fn new(lower: f64, upper: f64) -> Self {
+        Self::try_new(lower, upper).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid bounds.
+    pub fn try_new(lower: f64, upper: f64) -> Result<Self, PriorError> {
+        require(
+            0.0 < lower && lower < upper,
+            "log-uniform bounds must satisfy 0 < lower < upper",
+        )?;
+        Ok(Self { lower, upper })
+    }
+}
+
+impl PartialPrior for LogUniformPrior {
+    fn logprobability(&self, proposed: &f32) -> f64 {
+        let p: f64 = *proposed as f64;
+        if p < self.lower || p > self.upper {
+            return f64::NEG_INFINITY;
+        }
+        -p.ln() - (self.upper / self.lower).ln().ln()
+    }
+
+    fn initial_guess(&self) -> f64 {
+        (self.lower * self.upper).sqrt()
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rng.gen_range(self.lower.ln()..self.upper.ln())
+            .exp()
+            .clamp(self.lower, self.upper)
+    }
+
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        Some(PartialPriorSpec::LogUniform {
+            lower: self.lower,
+            upper: self.upper,
+        })
+    }
+}
+
+/// Log-normal prior given by its median and multiplicative (geometric)
+/// standard deviation, e.g. `LogNormalPrior::new(50.0, 3.0)` for "50 within
+/// a factor of 3".
+#[derive(Debug, Clone, Copy)]
+pub struct LogNormalPrior {
+    median: f64,
+    geometric_sd: f64,
+    distribution: LogNormal,
+}
+
+impl LogNormalPrior {
+    pub fn new(median: f64, geometric_sd: f64) -> Self {
+        Self::try_new(median, geometric_sd).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid parameters.
+    pub fn try_new(median: f64, geometric_sd: f64) -> Result<Self, PriorError> {
+        require(median > 0.0, "median must be positive")?;
+        require(
+            geometric_sd > 1.0,
+            "geometric standard deviation must exceed 1",
+        )?;
+        Ok(Self {
+            median,
+            geometric_sd,
+            distribution: LogNormal::new(median.ln(), geometric_sd.ln())
+                .map_err(|e| invalid(&e.to_string()))?,
+        })
+    }
+}
+
+impl PartialPrior for LogNormalPrior {
+    fn logprobability(&self, proposed: &f32) -> f64 {
+        let p: f64 = *proposed as f64;
+        if p <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
         self.distribution.ln_pdf(p)
     }
 
     fn initial_guess(&self) -> f64 {
-        self.distribution.mean().expect("Distribution has no mean?")
+        self.distribution.median()
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rand::distributions::Distribution::sample(&self.distribution, rng)
+    }
+
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        Some(PartialPriorSpec::LogNormal {
+            median: self.median,
+            geometric_sd: self.geometric_sd,
+        })
+    }
+}
+
+/// Continuous spike-and-slab prior for source selection: with probability
+/// `inclusion_probability` a parameter is drawn from the `slab`, otherwise
+/// from a narrow `Normal(0, spike_scale)` spike around zero.
+pub struct SpikeAndSlabPrior {
+    inclusion_probability: f64,
+    spike: Normal,
+    slab: Box<dyn PartialPrior>,
+}
+
+impl SpikeAndSlabPrior {
+    pub fn new(inclusion_probability: f64, spike_scale: f64, slab: Box<dyn PartialPrior>) -> Self {
+        Self::try_new(inclusion_probability, spike_scale, slab).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid parameters.
+    pub fn try_new(
+        inclusion_probability: f64,
+        spike_scale: f64,
+        slab: Box<dyn PartialPrior>,
+    ) -> Result<Self, PriorError> {
+        require(
+            0.0 < inclusion_probability && inclusion_probability < 1.0,
+            "inclusion probability must be in (0, 1)",
+        )?;
+        Ok(Self {
+            inclusion_probability,
+            spike: Normal::new(0.0, spike_scale)
+                .map_err(|_| invalid("spike scale must be positive"))?,
+            slab,
+        })
+    }
+
+    /// Probability that a parameter with this value came from the slab.
+    pub fn inclusion_probability(&self, value: &f32) -> f64 {
+        let slab = self.inclusion_probability.ln() + self.slab.logprobability(value);
+        let spike = (1.0 - self.inclusion_probability).ln() + self.spike.ln_pdf(*value as f64);
+        if slab == f64::NEG_INFINITY {
+            return 0.0;
+        }
+        1.0 / (1.0 + (spike - slab).exp())
+    }
+
+    /// Posterior inclusion probability: the average over posterior samples
+    /// of this parameter. `None` without samples.
+    pub fn posterior_inclusion_probability(&self, samples: &[f32]) -> Option<f64> {
+        if samples.is_empty() {
+            return None;
+        }
+        let total: f64 = samples.iter().map(|x| self.inclusion_probability(x)).sum();
+        Some(total / samples.len() as f64)
+    }
+}
+
+impl PartialPrior for SpikeAndSlabPrior {
+    fn logprobability(&self, proposed: &f32) -> f64 {
+        let slab = self.inclusion_probability.ln() + self.slab.logprobability(proposed);
+        let spike = (1.0 - self.inclusion_probability).ln() + self.spike.ln_pdf(*proposed as f64);
+        let max = slab.max(spike);
+        max + ((slab - max).exp() + (spike - max).exp()).ln()
+    }
+
+    fn initial_guess(&self) -> f64 {
+        self.slab.initial_guess()
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        if rng.gen_bool(self.inclusion_probability) {
+            self.slab.sample(rng)
+        } else {
+            rand::distributions::Distribution::sample(&self.spike, rng)
+        }
+    }
+
+    fn spec(&self) -> Option<PartialPriorSpec> {
+        Some(PartialPriorSpec::SpikeAndSlab {
+            inclusion_probability: self.inclusion_probability,
+            spike_scale: self.spike.std_dev()?,
+            slab: Box::new(self.slab.spec()?),
+        })
+    }
+}
+
+/// Prior over a discrete choice among `n` categories (e.g. transport
+/// scenario or release day), represented by the values `0..n`. Used with
+/// `InferenceProblem::with_marginalized_category`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "CategoricalPriorRaw")]
+pub struct CategoricalPrior {
+    log_probabilities: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct CategoricalPriorRaw {
+    log_probabilities: Vec<f64>,
+}
+
+impl TryFrom<CategoricalPriorRaw> for CategoricalPrior {
+    type Error = PriorError;
+
+    fn try_from(raw: CategoricalPriorRaw) -> Result<Self, PriorError> {
+        let total: f64 = raw.log_probabilities.iter().map(|l| l.exp()).sum();
+        require(
+            !raw.log_probabilities.is_empty() && (total - 1.0).abs() < 1e-9,
+            "category probabilities must sum to one",
+        )?;
+        Ok(Self {
+            log_probabilities: raw.log_probabilities,
+        })
+    }
+}
+
+impl CategoricalPrior {
+    /// Categories with the given (unnormalized) prior weights.
+    pub fn new(weights: Vec<f64>) -> Self {
+        Self::try_new(weights).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid weights.
+    pub fn try_new(weights: Vec<f64>) -> Result<Self, PriorError> {
+        require(!weights.is_empty(), "need at least one category")?;
+        require(
+            weights.iter().all(|w| *w >= 0.0),
+            "category weights must be non-negative",
+        )?;
+        let total: f64 = weights.iter().sum();
+        require(total > 0.0, "category weights must not all be zero")?;
+        Ok(Self {
+            log_probabilities: weights.iter().map(|w| (w / total).ln()).collect(),
+        })
+    }
+
+    /// Equally likely categories.
+    pub fn uniform(n_categories: usize) -> Self {
+        Self::new(vec![1.0; n_categories])
+    }
+
+    pub fn n_categories(&self) -> usize {
+        self.log_probabilities.len()
+    }
+
+    pub fn logprobability(&self, category: usize) -> f64 {
+        self.log_probabilities
+            .get(category)
+            .cloned()
+            .unwrap_or(f64::NEG_INFINITY)
+    }
+
+    /// Draw a category given per-category log weights (unnormalized). If the
+    /// largest weight is not finite, e.g. every weight is `-inf`, the
+    /// weights cannot be normalized and the draw is uniform.
+    pub(crate) fn draw(log_weights: &[f64], rng: &mut dyn RngCore) -> usize {
+        let max = log_weights
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if !max.is_finite() {
+            return rng.gen_range(0..log_weights.len());
+        }
+        let weights: Vec<f64> = log_weights.iter().map(|w| (w - max).exp()).collect();
+        let mut u = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for (k, w) in weights.iter().enumerate() {
+            if u < *w {
+                return k;
+            }
+            u -= w;
+        }
+        weights.len() - 1
+    }
+}
+
+/// Problems found while assigning priors and likelihood hyperparameters to
+/// named parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriorError {
+    /// A parameter of the problem has no prior.
+    Missing(String),
+    /// A prior was given more than once for the same parameter.
+    Duplicate(String),
+    /// A prior was given for a name that is not a parameter of the problem.
+    Unknown(String),
+    /// The prior covers a different number of parameters than were named.
+    DimensionMismatch { prior: usize, parameters: usize },
+    /// A likelihood hyperparameter is sampled at an index past the last
+    /// parameter.
+    HyperparameterIndex { index: usize, parameters: usize },
+    /// Observations inflate their error by a proposal entry that was not
+    /// declared as an error group.
+    UndeclaredErrorGroup(usize),
+    /// An error group is declared at an index that is not the parameter of
+    /// the same name.
+    ErrorGroupMismatch { group: String, index: usize },
+    /// A transform maps a different number of values than there are
+    /// continuous parameters.
+    TransformDimension { transform: usize, parameters: usize },
+    /// An empirical prior was asked to keep every 0th posterior sample.
+    ZeroThinning,
+    /// Fewer than two posterior samples are left to fit an empirical prior.
+    TooFewSamples(usize),
+    /// A parameter has the same value in every posterior sample, so no
+    /// kernel bandwidth can be chosen for it.
+    NoSpread(String),
+    /// A prior was given invalid parameters, or a description of the wrong
+    /// kind of prior.
+    Invalid(String),
+}
+
+impl std::fmt::Display for PriorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriorError::Missing(name) => write!(f, "no prior given for parameter '{}'", name),
+            PriorError::Duplicate(name) => {
+                write!(f, "more than one prior given for parameter '{}'", name)
+            }
+            PriorError::Unknown(name) => {
+                write!(f, "prior given for unknown parameter '{}'", name)
+            }
+            PriorError::DimensionMismatch { prior, parameters } => write!(
+                f,
+                "prior covers {} parameters but {} parameter names were given",
+                prior, parameters
+            ),
+            PriorError::HyperparameterIndex { index, parameters } => write!(
+                f,
+                "likelihood hyperparameter index {} is out of range for {} parameters",
+                index, parameters
+            ),
+            PriorError::UndeclaredErrorGroup(index) => write!(
+                f,
+                "observations inflate their error by parameter {} but no error group is declared for it",
+                index
+            ),
+            PriorError::ErrorGroupMismatch { group, index } => write!(
+                f,
+                "error group '{}' is declared at index {}, which is not the parameter of that name",
+                group, index
+            ),
+            PriorError::TransformDimension {
+                transform,
+                parameters,
+            } => write!(
+                f,
+                "transform produces {} values but there are {} continuous parameters",
+                transform, parameters
+            ),
+            PriorError::ZeroThinning => write!(f, "thinning must be at least 1"),
+            PriorError::TooFewSamples(samples) => write!(
+                f,
+                "need at least two posterior samples but {} are left after burn-in and thinning",
+                samples
+            ),
+            PriorError::NoSpread(name) => {
+                write!(f, "posterior samples of '{}' have no spread", name)
+            }
+            PriorError::Invalid(message) => write!(f, "invalid prior: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PriorError {}
+
+fn invalid(message: &str) -> PriorError {
+    PriorError::Invalid(message.to_string())
+}
+
+/// Fail with `message` unless `condition` holds.
+fn require(condition: bool, message: &str) -> Result<(), PriorError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(invalid(message))
+    }
+}
+
+/// Builds a `BasicPrior` by parameter name rather than position, so that
+/// reordering parameters cannot silently misassign priors.
+#[derive(Default)]
+pub struct PriorBuilder {
+    priors: Vec<(String, Box<dyn PartialPrior>)>,
+}
+
+impl PriorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, prior: impl PartialPrior + 'static) -> Self {
+        self.priors.push((name.to_string(), Box::new(prior)));
+        self
+    }
+
+    /// Order the priors to match `parameter_names`, checking that every
+    /// parameter has exactly one prior and every prior names a parameter.
+    /// A marginalized category takes a `CategoricalPrior` instead, so leave
+    /// it out of `parameter_names`, as in `InferenceProblem::try_new`.
+    pub fn build(self, parameter_names: &[String]) -> Result<BasicPrior, PriorError> {
+        let mut slots: Vec<Option<Box<dyn PartialPrior>>> =
+            parameter_names.iter().map(|_| None).collect();
+        for (name, prior) in self.priors {
+            let index = parameter_names
+                .iter()
+                .position(|n| *n == name)
+                .ok_or_else(|| PriorError::Unknown(name.clone()))?;
+            if slots[index].is_some() {
+                return Err(PriorError::Duplicate(name));
+            }
+            slots[index] = Some(prior);
+        }
+        let partial_priors = slots
+            .into_iter()
+            .zip(parameter_names)
+            .map(|(slot, name)| slot.ok_or_else(|| PriorError::Missing(name.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(BasicPrior::new(partial_priors))
     }
 }
 
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
 pub struct BasicPrior {
     pub partial_priors: Vec<Box<dyn PartialPrior>>,
 }
@@ -60,6 +1131,47 @@ impl Prior for BasicPrior {
                 .collect::<Vec<f32>>(),
         )
     }
+
+    /// Each walker starts from an independent draw from the prior.
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        (0..n_walkers)
+            .map(|_| {
+                Guess::new(
+                    &self
+                        .partial_priors
+                        .iter()
+                        .map(|x| x.sample(rng) as f32)
+                        .collect::<Vec<f32>>(),
+                )
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::Basic {
+            partial_priors: self
+                .partial_priors
+                .iter()
+                .map(|p| p.spec())
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for BasicPrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::Basic { partial_priors } => Ok(Self::new(
+                partial_priors
+                    .into_iter()
+                    .map(PartialPriorSpec::build)
+                    .collect::<Result<_, _>>()?,
+            )),
+            other => Err(unexpected("Basic", &other)),
+        }
+    }
 }
 
 impl Prob for BasicPrior {
@@ -72,36 +1184,1354 @@ impl Prob for BasicPrior {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use statrs::distribution::{Normal, Uniform};
+/// Correlated prior over several parameters, e.g. source strengths known to be
+/// anti-correlated from an inventory.
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
+pub struct MultivariateNormalPrior {
+    mean: Vec<f64>,
+    covariance: Vec<Vec<f64>>,
+    cholesky: Vec<Vec<f64>>,
+}
 
-    #[test]
-    fn dist_tests() {
-        let prior1 = IndependentPrior {
-            distribution: Uniform::new(0.0, 1.0).unwrap(),
-        };
-        assert_eq!(prior1.logprobability(&0.5), 0.0);
-        assert_eq!(prior1.logprobability(&1.0), 0.0);
-        assert_eq!(prior1.logprobability(&-0.1), f64::NEG_INFINITY)
+impl MultivariateNormalPrior {
+    pub fn new(mean: Vec<f64>, covariance: Vec<Vec<f64>>) -> Self {
+        Self::try_new(mean, covariance).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    #[test]
-    fn basic_prior() {
-        let basic = BasicPrior::new(vec![
-            Box::new(IndependentPrior {
-                distribution: Uniform::new(0.0, 1.0).unwrap(),
-            }),
-            Box::new(IndependentPrior {
-                distribution: Uniform::new(0.0, 1.0).unwrap(),
-            }),
-        ]);
-        let proposal1 = Guess::new(&[0.0, 1.0]);
-        let proposal2 = Guess::new(&[0.0, -0.1]);
-        let proposal3 = Guess::new(&[1.1, 0.0]);
-        assert_eq!(basic.logprobability(&proposal1), 0.0);
-        assert_eq!(basic.logprobability(&proposal2), f64::NEG_INFINITY);
-        assert_eq!(basic.logprobability(&proposal3), f64::NEG_INFINITY)
+    /// Like `new`, but fails rather than panics on an invalid covariance.
+    pub fn try_new(mean: Vec<f64>, covariance: Vec<Vec<f64>>) -> Result<Self, PriorError> {
+        require(
+            covariance.len() == mean.len() && covariance.iter().all(|row| row.len() == mean.len()),
+            "covariance must be square with one row per mean",
+        )?;
+        let cholesky =
+            cholesky(&covariance).ok_or_else(|| invalid("covariance must be positive definite"))?;
+        Ok(Self {
+            mean,
+            covariance,
+            cholesky,
+        })
+    }
+}
+
+/// Samples of the named parameters, in the order given, after discarding
+/// `skip` samples and keeping every `thinning`-th. At least two samples must
+/// be left.
+fn posterior_points(
+    posterior: &Posterior,
+    parameter_names: &[String],
+    skip: usize,
+    thinning: usize,
+) -> Result<Vec<Vec<f64>>, PriorError> {
+    let mut columns = Vec::with_capacity(parameter_names.len());
+    for (i, name) in parameter_names.iter().enumerate() {
+        if parameter_names[..i].contains(name) {
+            return Err(PriorError::Duplicate(name.clone()));
+        }
+        let column = posterior
+            .parameter_names()
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| PriorError::Missing(name.clone()))?;
+        columns.push(column);
+    }
+    if thinning == 0 {
+        return Err(PriorError::ZeroThinning);
+    }
+    let points: Vec<Vec<f64>> = posterior
+        .thinned_samples(skip, thinning)
+        .iter()
+        .map(|g| columns.iter().map(|c| g.values[*c] as f64).collect())
+        .collect();
+    if points.len() < 2 {
+        return Err(PriorError::TooFewSamples(points.len()));
+    }
+    Ok(points)
+}
+
+impl MultivariateNormalPrior {
+    /// Multivariate normal fitted to the samples of the named parameters of
+    /// an earlier posterior, in the order given, after discarding `skip`
+    /// samples and keeping every `thinning`-th. Fails if a name is repeated
+    /// or not in the posterior, or fewer than two samples are kept.
+    pub fn from_posterior(
+        posterior: &Posterior,
+        parameter_names: &[String],
+        skip: usize,
+        thinning: usize,
+    ) -> Result<Self, PriorError> {
+        let points = posterior_points(posterior, parameter_names, skip, thinning)?;
+        let n = points.len() as f64;
+        let dim = parameter_names.len();
+        let mut mean = vec![0.0; dim];
+        for point in points.iter() {
+            for (m, x) in mean.iter_mut().zip(point) {
+                *m += x / n;
+            }
+        }
+        let mut covariance = vec![vec![0.0; dim]; dim];
+        for point in points.iter() {
+            for i in 0..dim {
+                for j in 0..dim {
+                    covariance[i][j] += (point[i] - mean[i]) * (point[j] - mean[j]) / (n - 1.0);
+                }
+            }
+        }
+        Self::try_new(mean, covariance)
+    }
+}
+
+impl Prior for MultivariateNormalPrior {
+    fn logprobability(&self, proposal: &Guess) -> f64 {
+        let residual: Vec<f64> = proposal
+            .values
+            .iter()
+            .zip(&self.mean)
+            .map(|(x, m)| *x as f64 - m)
+            .collect();
+        mvn_logdensity(&self.cholesky, &residual)
+    }
+
+    fn initial_guess(&self) -> Guess {
+        Guess::new(&self.mean.iter().map(|x| *x as f32).collect::<Vec<f32>>())
+    }
+
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        let standard = Normal::new(0.0, 1.0).unwrap();
+        (0..n_walkers)
+            .map(|_| {
+                let z: Vec<f64> = self
+                    .mean
+                    .iter()
+                    .map(|_| rand::distributions::Distribution::sample(&standard, rng))
+                    .collect();
+                let values: Vec<f32> = self
+                    .cholesky
+                    .iter()
+                    .zip(&self.mean)
+                    .map(|(row, m)| {
+                        (m + row.iter().zip(&z).map(|(l, z)| l * z).sum::<f64>()) as f32
+                    })
+                    .collect();
+                Guess::new(&values)
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::MultivariateNormal {
+            mean: self.mean.clone(),
+            covariance: self.covariance.clone(),
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for MultivariateNormalPrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::MultivariateNormal { mean, covariance } => Self::try_new(mean, covariance),
+            other => Err(unexpected("MultivariateNormal", &other)),
+        }
+    }
+}
+
+impl Prob for MultivariateNormalPrior {
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.logprobability(params) as f32
+    }
+
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+}
+
+/// Gaussian kernel density estimate over the samples of an earlier
+/// posterior, so yesterday's posterior can serve as today's prior without
+/// assuming it is normal. Bandwidths follow Scott's rule per parameter.
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
+pub struct KernelDensityPrior {
+    points: Vec<Vec<f64>>,
+    bandwidths: Vec<f64>,
+}
+
+impl KernelDensityPrior {
+    /// Gaussian kernels with per-dimension `bandwidths` centred on `points`.
+    pub fn new(points: Vec<Vec<f64>>, bandwidths: Vec<f64>) -> Self {
+        Self::try_new(points, bandwidths).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid input.
+    pub fn try_new(points: Vec<Vec<f64>>, bandwidths: Vec<f64>) -> Result<Self, PriorError> {
+        require(
+            !points.is_empty(),
+            "kernel density prior needs at least one point",
+        )?;
+        require(
+            points.iter().all(|p| p.len() == bandwidths.len()),
+            "need one bandwidth per dimension of each point",
+        )?;
+        require(
+            bandwidths.iter().all(|h| *h > 0.0 && h.is_finite()),
+            "bandwidths must be positive and finite",
+        )?;
+        Ok(Self { points, bandwidths })
+    }
+
+    /// Build from the posterior samples of the named parameters, in the
+    /// order given, after discarding `skip` and keeping every `thinning`-th.
+    /// Each prior evaluation sums over all kept samples, so thin to a few
+    /// thousand points. Fails if a name is repeated or not in the posterior,
+    /// fewer than two samples are kept, or a parameter never varies.
+    pub fn from_posterior(
+        posterior: &Posterior,
+        parameter_names: &[String],
+        skip: usize,
+        thinning: usize,
+    ) -> Result<Self, PriorError> {
+        let points = posterior_points(posterior, parameter_names, skip, thinning)?;
+        let n = points.len() as f64;
+        let dim = parameter_names.len();
+        let factor = n.powf(-1.0 / (dim as f64 + 4.0));
+        let bandwidths = (0..dim)
+            .map(|i| {
+                let mean = points.iter().map(|p| p[i]).sum::<f64>() / n;
+                let var = points.iter().map(|p| (p[i] - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                let bandwidth = var.sqrt() * factor;
+                if bandwidth > 0.0 {
+                    Ok(bandwidth)
+                } else {
+                    Err(PriorError::NoSpread(parameter_names[i].clone()))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Self::try_new(points, bandwidths)
+    }
+}
+
+impl Prior for KernelDensityPrior {
+    fn logprobability(&self, proposal: &Guess) -> f64 {
+        let norm: f64 = self
+            .bandwidths
+            .iter()
+            .map(|h| -0.5 * (2.0 * std::f64::consts::PI).ln() - h.ln())
+            .sum::<f64>()
+            - (self.points.len() as f64).ln();
+        let terms: Vec<f64> = self
+            .points
+            .iter()
+            .map(|p| {
+                -0.5 * p
+                    .iter()
+                    .zip(&proposal.values)
+                    .zip(&self.bandwidths)
+                    .map(|((c, x), h)| ((*x as f64 - c) / h).powi(2))
+                    .sum::<f64>()
+            })
+            .collect();
+        let max = terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        norm + max + terms.iter().map(|t| (t - max).exp()).sum::<f64>().ln()
+    }
+
+    fn initial_guess(&self) -> Guess {
+        let n = self.points.len() as f64;
+        Guess::new(
+            &(0..self.bandwidths.len())
+                .map(|i| (self.points.iter().map(|p| p[i]).sum::<f64>() / n) as f32)
+                .collect::<Vec<f32>>(),
+        )
+    }
+
+    /// Each walker starts at a random kernel centre plus kernel noise.
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        let standard = Normal::new(0.0, 1.0).unwrap();
+        (0..n_walkers)
+            .map(|_| {
+                let centre = &self.points[rng.gen_range(0..self.points.len())];
+                Guess::new(
+                    &centre
+                        .iter()
+                        .zip(&self.bandwidths)
+                        .map(|(c, h)| {
+                            let z: f64 = rand::distributions::Distribution::sample(&standard, rng);
+                            (c + h * z) as f32
+                        })
+                        .collect::<Vec<f32>>(),
+                )
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::KernelDensity {
+            points: self.points.clone(),
+            bandwidths: self.bandwidths.clone(),
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for KernelDensityPrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::KernelDensity { points, bandwidths } => Self::try_new(points, bandwidths),
+            other => Err(unexpected("KernelDensity", &other)),
+        }
+    }
+}
+
+impl Prob for KernelDensityPrior {
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.logprobability(params) as f32
+    }
+
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+}
+
+/// Dirichlet prior over fractions that must be positive and sum to one, e.g.
+/// the share of total emission from each source. Pair with
+/// `transforms::SimplexTransform` so walkers move on an unconstrained space.
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
+pub struct DirichletPrior {
+    alpha: Vec<f64>,
+}
+
+impl DirichletPrior {
+    pub fn new(alpha: Vec<f64>) -> Self {
+        Self::try_new(alpha).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails rather than panics on invalid concentrations.
+    pub fn try_new(alpha: Vec<f64>) -> Result<Self, PriorError> {
+        require(alpha.len() >= 2, "a Dirichlet needs at least two fractions")?;
+        require(
+            alpha.iter().all(|a| *a > 0.0),
+            "concentrations must be positive",
+        )?;
+        Ok(Self { alpha })
+    }
+}
+
+impl Prior for DirichletPrior {
+    fn logprobability(&self, proposal: &Guess) -> f64 {
+        let total: f64 = proposal.values.iter().map(|x| *x as f64).sum();
+        // fractions are stored as f32, so only require the sum to that precision
+        if proposal.values.iter().any(|x| *x <= 0.0) || (total - 1.0).abs() > 1e-4 {
+            return f64::NEG_INFINITY;
+        }
+        ln_gamma(self.alpha.iter().sum())
+            + self
+                .alpha
+                .iter()
+                .zip(proposal.values.iter())
+                .map(|(a, x)| (a - 1.0) * (*x as f64).ln() - ln_gamma(*a))
+                .sum::<f64>()
+    }
+
+    fn initial_guess(&self) -> Guess {
+        let total: f64 = self.alpha.iter().sum();
+        Guess::new(
+            &self
+                .alpha
+                .iter()
+                .map(|a| (a / total) as f32)
+                .collect::<Vec<f32>>(),
+        )
+    }
+
+    /// Draws via normalized Gamma variates.
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        (0..n_walkers)
+            .map(|_| {
+                let draws: Vec<f64> = self
+                    .alpha
+                    .iter()
+                    .map(|a| {
+                        let gamma = Gamma::new(*a, 1.0).unwrap();
+                        rand::distributions::Distribution::sample(&gamma, rng)
+                            .max(f64::MIN_POSITIVE)
+                    })
+                    .collect();
+                let total: f64 = draws.iter().sum();
+                Guess::new(
+                    &draws
+                        .iter()
+                        .map(|x| (x / total) as f32)
+                        .collect::<Vec<f32>>(),
+                )
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::Dirichlet {
+            alpha: self.alpha.clone(),
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for DirichletPrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::Dirichlet { alpha } => Self::try_new(alpha),
+            other => Err(unexpected("Dirichlet", &other)),
+        }
+    }
+}
+
+impl Prob for DirichletPrior {
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.logprobability(params) as f32
+    }
+
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+}
+
+/// Population distribution shared by the members of a `HierarchicalPrior`,
+/// parameterized by a location and a scale hyperparameter.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Population {
+    /// `member ~ Normal(location, scale)`
+    Normal,
+    /// `ln(member) ~ Normal(location, scale)`
+    LogNormal,
+}
+
+impl Population {
+    /// `-inf` for hyperparameters that give no valid distribution.
+    fn logprobability(&self, member: f64, location: f64, scale: f64) -> f64 {
+        let density = match self {
+            Population::Normal => Normal::new(location, scale).map(|d| d.ln_pdf(member)),
+            Population::LogNormal => {
+                if member <= 0.0 {
+                    return f64::NEG_INFINITY;
+                }
+                LogNormal::new(location, scale).map(|d| d.ln_pdf(member))
+            }
+        };
+        density.unwrap_or(f64::NEG_INFINITY)
+    }
+
+    fn typical(&self, location: f64) -> f64 {
+        match self {
+            Population::Normal => location,
+            Population::LogNormal => location.exp(),
+        }
+    }
+
+    fn sample(&self, location: f64, scale: f64, rng: &mut dyn RngCore) -> f64 {
+        let z: f64 =
+            rand::distributions::Distribution::sample(&Normal::new(0.0, 1.0).unwrap(), rng);
+        match self {
+            Population::Normal => location + scale * z,
+            Population::LogNormal => (location + scale * z).exp(),
+        }
+    }
+}
+
+/// Members drawn from a common population whose location and scale are
+/// themselves sampled, e.g. source strengths `s_i ~ LogNormal(mu, tau)`.
+/// Covers `2 + n_members` consecutive parameters: location, scale, then the
+/// members. Combine with other parameters through `CompositePrior`.
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
+pub struct HierarchicalPrior {
+    population: Population,
+    location_prior: Box<dyn PartialPrior>,
+    scale_prior: Box<dyn PartialPrior>,
+    n_members: usize,
+}
+
+impl HierarchicalPrior {
+    pub fn new(
+        population: Population,
+        location_prior: Box<dyn PartialPrior>,
+        scale_prior: Box<dyn PartialPrior>,
+        n_members: usize,
+    ) -> Self {
+        Self::try_new(population, location_prior, scale_prior, n_members)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(
+        population: Population,
+        location_prior: Box<dyn PartialPrior>,
+        scale_prior: Box<dyn PartialPrior>,
+        n_members: usize,
+    ) -> Result<Self, PriorError> {
+        require(
+            n_members > 0,
+            "hierarchical prior needs at least one member",
+        )?;
+        Ok(Self {
+            population,
+            location_prior,
+            scale_prior,
+            n_members,
+        })
+    }
+
+    /// Names for this block's parameters in order: `{prefix}_location`,
+    /// `{prefix}_scale`, then `{prefix}_0` onwards.
+    pub fn parameter_names(&self, prefix: &str) -> Vec<String> {
+        let mut names = vec![format!("{}_location", prefix), format!("{}_scale", prefix)];
+        names.extend((0..self.n_members).map(|i| format!("{}_{}", prefix, i)));
+        names
+    }
+}
+
+impl Prior for HierarchicalPrior {
+    fn logprobability(&self, proposal: &Guess) -> f64 {
+        if proposal.values.len() != 2 + self.n_members {
+            return f64::NEG_INFINITY;
+        }
+        let location = proposal.values[0];
+        let scale = proposal.values[1];
+        let hyper =
+            self.location_prior.logprobability(&location) + self.scale_prior.logprobability(&scale);
+        if !hyper.is_finite() {
+            return hyper;
+        }
+        hyper
+            + proposal.values[2..]
+                .iter()
+                .map(|x| {
+                    self.population
+                        .logprobability(*x as f64, location as f64, scale as f64)
+                })
+                .sum::<f64>()
+    }
+
+    fn initial_guess(&self) -> Guess {
+        let location = self.location_prior.initial_guess();
+        let mut values = vec![location as f32, self.scale_prior.initial_guess() as f32];
+        values.extend(vec![
+            self.population.typical(location) as f32;
+            self.n_members
+        ]);
+        Guess::new(&values)
+    }
+
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        (0..n_walkers)
+            .map(|_| {
+                let location = self.location_prior.sample(rng);
+                let scale = self.scale_prior.sample(rng);
+                let mut values = vec![location as f32, scale as f32];
+                for _ in 0..self.n_members {
+                    values.push(self.population.sample(location, scale, rng) as f32);
+                }
+                Guess::new(&values)
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::Hierarchical {
+            population: self.population,
+            location_prior: self.location_prior.spec()?,
+            scale_prior: self.scale_prior.spec()?,
+            n_members: self.n_members,
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for HierarchicalPrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::Hierarchical {
+                population,
+                location_prior,
+                scale_prior,
+                n_members,
+            } => Self::try_new(
+                population,
+                location_prior.build()?,
+                scale_prior.build()?,
+                n_members,
+            ),
+            other => Err(unexpected("Hierarchical", &other)),
+        }
+    }
+}
+
+impl Prob for HierarchicalPrior {
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.logprobability(params) as f32
+    }
+
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+}
+
+/// Independent blocks of parameters, each with its own `Prior`. Blocks cover
+/// consecutive parameters in order, with each block's size taken from the
+/// length of its initial guess.
+#[derive(Deserialize)]
+#[serde(try_from = "PriorSpec")]
+pub struct CompositePrior {
+    components: Vec<Box<dyn Prior>>,
+    dimensions: Vec<usize>,
+}
+
+impl CompositePrior {
+    pub fn new(components: Vec<Box<dyn Prior>>) -> Self {
+        let dimensions = components
+            .iter()
+            .map(|c| c.initial_guess().values.len())
+            .collect();
+        Self {
+            components,
+            dimensions,
+        }
+    }
+}
+
+impl Prior for CompositePrior {
+    fn logprobability(&self, proposal: &Guess) -> f64 {
+        let mut start = 0;
+        let mut lp = 0.0;
+        for (component, dim) in self.components.iter().zip(&self.dimensions) {
+            lp += component.logprobability(&Guess::new(&proposal.values[start..start + dim]));
+            start += dim;
+        }
+        lp
+    }
+
+    fn initial_guess(&self) -> Guess {
+        Guess::new(
+            &self
+                .components
+                .iter()
+                .flat_map(|c| c.initial_guess().values)
+                .collect::<Vec<f32>>(),
+        )
+    }
+
+    fn initial_ensemble(&self, n_walkers: usize, rng: &mut dyn RngCore) -> Vec<Guess> {
+        let blocks: Vec<Vec<Guess>> = self
+            .components
+            .iter()
+            .map(|c| c.initial_ensemble(n_walkers, rng))
+            .collect();
+        (0..n_walkers)
+            .map(|i| {
+                Guess::new(
+                    &blocks
+                        .iter()
+                        .flat_map(|b| b[i].values.iter().cloned())
+                        .collect::<Vec<f32>>(),
+                )
+            })
+            .collect()
+    }
+
+    fn spec(&self) -> Option<PriorSpec> {
+        Some(PriorSpec::Composite {
+            components: self
+                .components
+                .iter()
+                .map(|c| c.spec())
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<PriorSpec> for CompositePrior {
+    type Error = PriorError;
+
+    fn try_from(spec: PriorSpec) -> Result<Self, PriorError> {
+        match spec {
+            PriorSpec::Composite { components } => Ok(Self::new(
+                components
+                    .into_iter()
+                    .map(PriorSpec::build)
+                    .collect::<Result<_, _>>()?,
+            )),
+            other => Err(unexpected("Composite", &other)),
+        }
+    }
+}
+
+impl Prob for CompositePrior {
+    fn lnprior(&self, params: &Guess) -> f32 {
+        self.logprobability(params) as f32
+    }
+
+    fn lnlike(&self, _params: &Guess) -> f32 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use statrs::assert_almost_eq;
+    use statrs::distribution::{Cauchy, Uniform};
+
+    #[test]
+    fn dist_tests() {
+        let prior1 = IndependentPrior {
+            distribution: Uniform::new(0.0, 1.0).unwrap(),
+        };
+        assert_eq!(prior1.logprobability(&0.5), 0.0);
+        assert_eq!(prior1.logprobability(&1.0), 0.0);
+        assert_eq!(prior1.logprobability(&-0.1), f64::NEG_INFINITY)
+    }
+
+    #[test]
+    fn basic_prior() {
+        let basic = BasicPrior::new(vec![
+            Box::new(IndependentPrior {
+                distribution: Uniform::new(0.0, 1.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: Uniform::new(0.0, 1.0).unwrap(),
+            }),
+        ]);
+        let proposal1 = Guess::new(&[0.0, 1.0]);
+        let proposal2 = Guess::new(&[0.0, -0.1]);
+        let proposal3 = Guess::new(&[1.1, 0.0]);
+        assert_eq!(basic.logprobability(&proposal1), 0.0);
+        assert_eq!(basic.logprobability(&proposal2), f64::NEG_INFINITY);
+        assert_eq!(basic.logprobability(&proposal3), f64::NEG_INFINITY)
+    }
+
+    #[test]
+    fn multivariate_normal_prior() {
+        let prior =
+            MultivariateNormalPrior::new(vec![1.0, 2.0], vec![vec![1.0, -0.8], vec![-0.8, 1.0]]);
+        assert_eq!(prior.initial_guess().values, vec![1.0, 2.0]);
+        // anti-correlated deviations are favored
+        let opposite = prior.logprobability(&Guess::new(&[2.0, 1.0]));
+        let together = prior.logprobability(&Guess::new(&[2.0, 3.0]));
+        assert!(opposite > together);
+
+        let independent =
+            MultivariateNormalPrior::new(vec![1.0, 2.0], vec![vec![1.0, 0.0], vec![0.0, 4.0]]);
+        let expected =
+            Normal::new(1.0, 1.0).unwrap().ln_pdf(0.5) + Normal::new(2.0, 2.0).unwrap().ln_pdf(3.0);
+        assert_almost_eq!(
+            independent.logprobability(&Guess::new(&[0.5, 3.0])),
+            expected,
+            1e-6
+        );
+    }
+
+    #[test]
+    fn composite_prior() {
+        let mvn =
+            MultivariateNormalPrior::new(vec![1.0, 2.0], vec![vec![1.0, 0.5], vec![0.5, 1.0]]);
+        let mvn_lp = mvn.logprobability(&Guess::new(&[1.5, 2.5]));
+        let composite = CompositePrior::new(vec![
+            Box::new(mvn),
+            Box::new(BasicPrior::new(vec![Box::new(IndependentPrior {
+                distribution: Uniform::new(0.0, 1.0).unwrap(),
+            })])),
+        ]);
+        assert_eq!(composite.initial_guess().values, vec![1.0, 2.0, 0.5]);
+        assert_almost_eq!(
+            composite.logprobability(&Guess::new(&[1.5, 2.5, 0.5])),
+            mvn_lp,
+            1e-12
+        );
+        assert_eq!(
+            composite.logprobability(&Guess::new(&[1.5, 2.5, 1.5])),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn dirichlet_prior() {
+        let prior = DirichletPrior::new(vec![2.0, 3.0, 5.0]);
+        assert_eq!(prior.initial_guess().values, vec![0.2, 0.3, 0.5]);
+        // Dirichlet(1, 1) is uniform on the line x1 + x2 = 1
+        let flat = DirichletPrior::new(vec![1.0, 1.0]);
+        assert_almost_eq!(flat.logprobability(&Guess::new(&[0.3, 0.7])), 0.0, 1e-12);
+        // Dirichlet(2, 3) marginal is Beta(2, 3)
+        let beta = DirichletPrior::new(vec![2.0, 3.0]);
+        let expected = statrs::distribution::Beta::new(2.0, 3.0)
+            .unwrap()
+            .ln_pdf(0.25);
+        assert_almost_eq!(
+            beta.logprobability(&Guess::new(&[0.25, 0.75])),
+            expected,
+            1e-6
+        );
+        assert_eq!(
+            prior.logprobability(&Guess::new(&[0.2, 0.3, 0.6])),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(
+            prior.logprobability(&Guess::new(&[-0.2, 0.7, 0.5])),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn log_uniform_prior() {
+        let prior = LogUniformPrior::new(1.0, 100.0);
+        assert_almost_eq!(prior.initial_guess(), 10.0, 1e-12);
+        // equal mass per decade
+        let density = |x: f64| prior.logprobability(&(x as f32)).exp();
+        assert_almost_eq!(density(1.0) * 1.0, density(10.0) * 10.0, 1e-6);
+        assert_almost_eq!(density(1.0), 1.0 / 100.0_f64.ln(), 1e-12);
+        assert_eq!(prior.logprobability(&0.5), f64::NEG_INFINITY);
+        assert_eq!(prior.logprobability(&101.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn log_normal_prior() {
+        let prior = LogNormalPrior::new(50.0, 3.0);
+        assert_almost_eq!(prior.initial_guess(), 50.0, 1e-9);
+        let expected = LogNormal::new(50.0_f64.ln(), 3.0_f64.ln())
+            .unwrap()
+            .ln_pdf(20.0);
+        assert_almost_eq!(prior.logprobability(&20.0), expected, 1e-12);
+        assert_eq!(prior.logprobability(&-1.0), f64::NEG_INFINITY);
+        assert_eq!(prior.logprobability(&0.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn truncated_prior() {
+        let normal = Normal::new(50.0, 30.0).unwrap();
+        let prior = IndependentPrior {
+            distribution: normal,
+        }
+        .truncated(0.0, f64::INFINITY);
+        assert_eq!(prior.logprobability(&-0.1), f64::NEG_INFINITY);
+        let expected = normal.ln_pdf(10.0) - normal.sf(0.0).ln();
+        assert_almost_eq!(prior.logprobability(&10.0), expected, 1e-12);
+        assert!(prior.initial_guess() > 50.0);
+
+        // initial guess stays inside a window far from the mode
+        let tail = TruncatedPrior::new(normal, 200.0, 210.0);
+        let guess = tail.initial_guess();
+        assert!((200.0..=210.0).contains(&guess));
+        assert!(tail.logprobability(&(guess as f32)).is_finite());
+        assert_eq!(tail.logprobability(&211.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn initial_guess_without_mean() {
+        let prior = IndependentPrior {
+            distribution: Cauchy::new(3.0, 1.0).unwrap(),
+        };
+        assert_almost_eq!(prior.initial_guess(), 3.0, 1e-9);
+        let uniform = IndependentPrior {
+            distribution: Uniform::new(10.0, 200.0).unwrap(),
+        };
+        assert_almost_eq!(uniform.initial_guess(), 105.0, 1e-9);
+    }
+
+    #[test]
+    fn dispersed_initial_ensemble() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let basic = BasicPrior::new(vec![
+            Box::new(IndependentPrior {
+                distribution: Uniform::new(10.0, 200.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: Cauchy::new(0.0, 1.0).unwrap(),
+            }),
+            Box::new(LogUniformPrior::new(1.0, 1000.0)),
+            Box::new(
+                IndependentPrior {
+                    distribution: Normal::new(50.0, 30.0).unwrap(),
+                }
+                .truncated(0.0, 60.0),
+            ),
+        ]);
+        let ensemble = basic.initial_ensemble(200, &mut rng);
+        assert_eq!(ensemble.len(), 200);
+        for walker in ensemble.iter() {
+            assert!(basic.logprobability(walker).is_finite());
+        }
+        // uniform walkers cover the prior rather than sitting at the midpoint
+        let first: Vec<f32> = ensemble.iter().map(|g| g.values[0]).collect();
+        assert!(first.iter().any(|x| *x < 50.0) && first.iter().any(|x| *x > 160.0));
+
+        let composite = CompositePrior::new(vec![
+            Box::new(MultivariateNormalPrior::new(
+                vec![1.0, 2.0],
+                vec![vec![1.0, 0.5], vec![0.5, 1.0]],
+            )),
+            Box::new(DirichletPrior::new(vec![1.0, 2.0, 3.0])),
+        ]);
+        let ensemble = composite.initial_ensemble(100, &mut rng);
+        for walker in ensemble.iter() {
+            assert_eq!(walker.values.len(), 5);
+            assert!(composite.logprobability(walker).is_finite());
+        }
+        let mean: f32 = ensemble.iter().map(|g| g.values[0]).sum::<f32>() / 100.0;
+        assert_almost_eq!(mean as f64, 1.0, 0.4);
+
+        // priors without their own `sample` still spread the walkers apart
+        struct Flat;
+        impl PartialPrior for Flat {
+            fn logprobability(&self, _proposed: &f32) -> f64 {
+                0.0
+            }
+
+            fn initial_guess(&self) -> f64 {
+                1000.0
+            }
+        }
+        let ensemble = BasicPrior::new(vec![Box::new(Flat)]).initial_ensemble(10, &mut rng);
+        let first: Vec<f32> = ensemble.iter().map(|g| g.values[0]).collect();
+        assert!(first.iter().all(|x| (x - 1000.0).abs() < 0.1));
+        assert!(first.iter().any(|x| *x != first[0]));
+
+        // the default ensemble of a custom prior follows the seed
+        struct Fixed;
+        impl Prior for Fixed {
+            fn logprobability(&self, _proposal: &Guess) -> f64 {
+                0.0
+            }
+
+            fn initial_guess(&self) -> Guess {
+                Guess::new(&[1.0, 2.0])
+            }
+        }
+        let seeded = |seed| {
+            Fixed
+                .initial_ensemble(10, &mut StdRng::seed_from_u64(seed))
+                .into_iter()
+                .map(|g| g.values)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seeded(5), seeded(5));
+        assert_ne!(seeded(5), seeded(6));
+    }
+
+    #[test]
+    fn hierarchical_prior() {
+        let prior = HierarchicalPrior::new(
+            Population::LogNormal,
+            Box::new(IndependentPrior {
+                distribution: Normal::new(2.0, 1.0).unwrap(),
+            }),
+            Box::new(LogUniformPrior::new(0.1, 10.0)),
+            3,
+        );
+        assert_eq!(
+            prior.parameter_names("source"),
+            vec![
+                "source_location",
+                "source_scale",
+                "source_0",
+                "source_1",
+                "source_2"
+            ]
+        );
+        let guess = prior.initial_guess();
+        assert_eq!(guess.values.len(), 5);
+        assert!(prior.logprobability(&guess).is_finite());
+
+        let proposal = Guess::new(&[2.0, 0.5, 5.0, 8.0, 10.0]);
+        let population = LogNormal::new(2.0, 0.5).unwrap();
+        let expected = Normal::new(2.0, 1.0).unwrap().ln_pdf(2.0)
+            + LogUniformPrior::new(0.1, 10.0).logprobability(&0.5)
+            + population.ln_pdf(5.0)
+            + population.ln_pdf(8.0)
+            + population.ln_pdf(10.0);
+        assert_almost_eq!(prior.logprobability(&proposal), expected, 1e-9);
+
+        // members depend on the sampled hyperparameters
+        let tight = Guess::new(&[2.0, 0.2, 5.0, 8.0, 10.0]);
+        assert_ne!(
+            prior.logprobability(&tight),
+            prior.logprobability(&proposal)
+        );
+        assert_eq!(
+            prior.logprobability(&Guess::new(&[2.0, 0.5, -1.0, 8.0, 10.0])),
+            f64::NEG_INFINITY
+        );
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for walker in prior.initial_ensemble(50, &mut rng) {
+            assert!(prior.logprobability(&walker).is_finite());
+        }
+
+        // proposals must cover the hyperparameters and every member
+        for values in [
+            &[2.0][..],
+            &[2.0, 0.5, 5.0, 8.0],
+            &[2.0, 0.5, 5.0, 8.0, 10.0, 1.0],
+        ] {
+            assert_eq!(prior.logprobability(&Guess::new(values)), f64::NEG_INFINITY);
+        }
+        // invalid hyperparameters give -inf rather than a panic
+        assert_eq!(
+            Population::Normal.logprobability(1.0, f64::NAN, 1.0),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(
+            Population::LogNormal.logprobability(1.0, 0.0, -1.0),
+            f64::NEG_INFINITY
+        );
+        let empty = HierarchicalPrior::try_new(
+            Population::Normal,
+            Box::new(LogUniformPrior::new(0.1, 10.0)),
+            Box::new(LogUniformPrior::new(0.1, 10.0)),
+            0,
+        );
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn spike_and_slab_prior() {
+        let slab = Uniform::new(0.0, 100.0).unwrap();
+        let prior =
+            SpikeAndSlabPrior::new(0.3, 0.01, Box::new(IndependentPrior { distribution: slab }));
+        let spike = Normal::new(0.0, 0.01).unwrap();
+        let expected = (0.3 * slab.pdf(5.0) + 0.7 * spike.pdf(5.0)).ln();
+        assert_almost_eq!(prior.logprobability(&5.0), expected, 1e-12);
+        // outside the slab support only the spike contributes
+        assert_almost_eq!(
+            prior.logprobability(&-0.005),
+            0.7_f64.ln() + spike.ln_pdf(-0.005_f32 as f64),
+            1e-12
+        );
+
+        assert!(prior.inclusion_probability(&5.0) > 0.999);
+        assert!(prior.inclusion_probability(&0.001) < 0.01);
+        assert_eq!(prior.inclusion_probability(&-1.0), 0.0);
+        assert_almost_eq!(
+            prior
+                .posterior_inclusion_probability(&[5.0, 10.0, -1.0, -2.0])
+                .unwrap(),
+            0.5,
+            1e-3
+        );
+        assert_eq!(prior.posterior_inclusion_probability(&[]), None);
+    }
+
+    fn normal_posterior(rng: &mut StdRng) -> Posterior {
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let samples = (0..4000)
+            .map(|_| {
+                let a: f64 = rand::distributions::Distribution::sample(&normal, rng);
+                let b: f64 = rand::distributions::Distribution::sample(&normal, rng);
+                // x ~ N(10, 2), y = -x + noise
+                let x = 10.0 + 2.0 * a;
+                Guess::new(&[x as f32, (-x + 0.5 * b) as f32])
+            })
+            .collect();
+        Posterior::new(vec!["x".to_string(), "y".to_string()], samples)
+    }
+
+    #[test]
+    fn multivariate_normal_from_posterior() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let posterior = normal_posterior(&mut rng);
+        let names = ["x".to_string(), "y".to_string()];
+        let prior = MultivariateNormalPrior::from_posterior(&posterior, &names, 0, 1).unwrap();
+        let mean = prior.initial_guess();
+        assert_almost_eq!(mean.values[0] as f64, 10.0, 0.2);
+        assert_almost_eq!(mean.values[1] as f64, -10.0, 0.2);
+        // anti-correlation is carried over
+        let along = prior.logprobability(&Guess::new(&[12.0, -12.0]));
+        let across = prior.logprobability(&Guess::new(&[12.0, -8.0]));
+        assert!(along > across);
+
+        // columns are selected and reordered by name
+        let reordered = ["y".to_string(), "x".to_string()];
+        let prior = MultivariateNormalPrior::from_posterior(&posterior, &reordered, 0, 1).unwrap();
+        assert_almost_eq!(prior.initial_guess().values[0] as f64, -10.0, 0.2);
+        let prior = MultivariateNormalPrior::from_posterior(&posterior, &names[..1], 0, 1).unwrap();
+        assert_eq!(prior.initial_guess().values.len(), 1);
+        assert_eq!(
+            MultivariateNormalPrior::from_posterior(&posterior, &["z".to_string()], 0, 1).err(),
+            Some(PriorError::Missing("z".to_string()))
+        );
+        let repeated = ["x".to_string(), "x".to_string()];
+        assert_eq!(
+            MultivariateNormalPrior::from_posterior(&posterior, &repeated, 0, 1).err(),
+            Some(PriorError::Duplicate("x".to_string()))
+        );
+
+        // one sample is too few to fit, and thinning must keep samples
+        assert_eq!(
+            MultivariateNormalPrior::from_posterior(&posterior, &names, 3999, 1).err(),
+            Some(PriorError::TooFewSamples(1))
+        );
+        assert_eq!(
+            MultivariateNormalPrior::from_posterior(&posterior, &names, 0, 0).err(),
+            Some(PriorError::ZeroThinning)
+        );
+    }
+
+    #[test]
+    fn kernel_density_from_posterior() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let posterior = normal_posterior(&mut rng);
+        let names = ["x".to_string(), "y".to_string()];
+        let prior = KernelDensityPrior::from_posterior(&posterior, &names, 0, 4).unwrap();
+        let along = prior.logprobability(&Guess::new(&[12.0, -12.0]));
+        let across = prior.logprobability(&Guess::new(&[12.0, -8.0]));
+        assert!(along > across);
+        assert_almost_eq!(prior.initial_guess().values[0] as f64, 10.0, 0.2);
+        for walker in prior.initial_ensemble(20, &mut rng) {
+            assert!(prior.logprobability(&walker).is_finite());
+        }
+
+        let empty = PriorSpec::KernelDensity {
+            points: vec![],
+            bandwidths: vec![1.0],
+        };
+        assert!(KernelDensityPrior::try_from(empty).is_err());
+
+        let single = Posterior::new(names.to_vec(), vec![Guess::new(&[1.0, 2.0])]);
+        assert_eq!(
+            KernelDensityPrior::from_posterior(&single, &names, 0, 1).err(),
+            Some(PriorError::TooFewSamples(1))
+        );
+        assert_eq!(
+            KernelDensityPrior::from_posterior(&posterior, &names, 0, 0).err(),
+            Some(PriorError::ZeroThinning)
+        );
+        // a constant column leaves no bandwidth
+        let constant = Posterior::new(
+            names.to_vec(),
+            vec![Guess::new(&[1.0, 2.0]), Guess::new(&[1.0, 3.0])],
+        );
+        assert_eq!(
+            KernelDensityPrior::from_posterior(&constant, &names, 0, 1).err(),
+            Some(PriorError::NoSpread("x".to_string()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "needs at least one point")]
+    fn empty_kernel_density() {
+        KernelDensityPrior::new(vec![], vec![]);
+    }
+
+    #[test]
+    fn categorical_prior() {
+        let prior = CategoricalPrior::new(vec![1.0, 3.0]);
+        assert_eq!(prior.n_categories(), 2);
+        assert_almost_eq!(prior.logprobability(1), 0.75_f64.ln(), 1e-12);
+        assert_eq!(prior.logprobability(2), f64::NEG_INFINITY);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let draws: Vec<usize> = (0..2000)
+            .map(|_| CategoricalPrior::draw(&[0.25_f64.ln(), 0.75_f64.ln()], &mut rng))
+            .collect();
+        let ones = draws.iter().filter(|k| **k == 1).count() as f64 / 2000.0;
+        assert_almost_eq!(ones, 0.75, 0.05);
+        assert_eq!(
+            CategoricalPrior::draw(&[f64::NEG_INFINITY, 0.0], &mut rng),
+            1
+        );
+
+        let impossible = [f64::NEG_INFINITY; 2];
+        let ones = (0..2000)
+            .filter(|_| CategoricalPrior::draw(&impossible, &mut rng) == 1)
+            .count() as f64
+            / 2000.0;
+        assert_almost_eq!(ones, 0.5, 0.05);
+    }
+
+    #[test]
+    fn statrs_distribution_specs() {
+        // every statrs distribution round-trips, truncated or not
+        let priors: Vec<Box<dyn PartialPrior>> = vec![
+            Box::new(IndependentPrior {
+                distribution: LogNormal::new(0.5, 0.75).unwrap(),
+            }),
+            Box::new(
+                IndependentPrior {
+                    distribution: Weibull::new(2.0, 1.0).unwrap(),
+                }
+                .truncated(0.5, 2.0),
+            ),
+            Box::new(IndependentPrior {
+                distribution: Pareto::new(1.0, 3.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: ChiSquared::new(3.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: Chi::new(2.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: Erlang::new(3, 2.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: InverseGamma::new(3.0, 2.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: Triangular::new(0.0, 4.0, 1.0).unwrap(),
+            }),
+            Box::new(IndependentPrior {
+                distribution: FisherSnedecor::new(5.0, 7.0).unwrap(),
+            }),
+        ];
+        for prior in priors {
+            let json = serde_json::to_string(&prior).unwrap();
+            let restored: Box<dyn PartialPrior> = serde_json::from_str(&json).unwrap();
+            assert_almost_eq!(restored.initial_guess(), prior.initial_guess(), 1e-9);
+            assert_almost_eq!(
+                restored.logprobability(&1.5),
+                prior.logprobability(&1.5),
+                1e-9
+            );
+        }
+        let log_normal: Box<dyn PartialPrior> = Box::new(IndependentPrior {
+            distribution: LogNormal::new(0.0, 1.0).unwrap(),
+        });
+        assert_almost_eq!(log_normal.initial_guess(), 1.0, 1e-9);
+
+        // a custom distribution works but cannot be saved
+        struct Spike;
+        impl rand::distributions::Distribution<f64> for Spike {
+            fn sample<R: Rng + ?Sized>(&self, _rng: &mut R) -> f64 {
+                3.0
+            }
+        }
+        impl Distribution<f64> for Spike {
+            fn mean(&self) -> Option<f64> {
+                Some(3.0)
+            }
+        }
+        impl Continuous<f64, f64> for Spike {
+            fn pdf(&self, x: f64) -> f64 {
+                if x == 3.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+
+            fn ln_pdf(&self, x: f64) -> f64 {
+                self.pdf(x).ln()
+            }
+        }
+        let custom: Box<dyn PartialPrior> = Box::new(IndependentPrior {
+            distribution: Spike,
+        });
+        assert_eq!(custom.initial_guess(), 3.0);
+        assert_eq!(custom.sample(&mut StdRng::seed_from_u64(1)), 3.0);
+        assert!(serde_json::to_string(&custom).is_err());
+    }
+
+    #[test]
+    fn composite_prior_json() {
+        let prior = CompositePrior::new(vec![
+            Box::new(HierarchicalPrior::new(
+                Population::LogNormal,
+                Box::new(IndependentPrior {
+                    distribution: Cauchy::new(2.0, 1.0).unwrap(),
+                }),
+                Box::new(LogUniformPrior::new(0.1, 10.0)),
+                2,
+            )),
+            Box::new(MultivariateNormalPrior::new(
+                vec![1.0, 2.0],
+                vec![vec![1.0, 0.5], vec![0.5, 1.0]],
+            )),
+            Box::new(DirichletPrior::new(vec![1.0, 2.0])),
+        ]);
+        let json = serde_json::to_string(&prior).unwrap();
+        let reloaded: CompositePrior = serde_json::from_str(&json).unwrap();
+        let proposal = Guess::new(&[2.0, 0.5, 5.0, 8.0, 1.5, 2.5, 0.25, 0.75]);
+        assert_eq!(
+            reloaded.logprobability(&proposal),
+            prior.logprobability(&proposal)
+        );
+        assert_eq!(reloaded.spec(), prior.spec());
+
+        // the wrong kind of prior is rejected
+        assert!(serde_json::from_str::<BasicPrior>(&json).is_err());
+        let invalid = r#"{"type": "Basic", "partial_priors": [
+            {"type": "Independent", "distribution": {"type": "Normal", "mean": 0.0, "std_dev": -1.0}}
+        ]}"#;
+        assert!(serde_json::from_str::<BasicPrior>(invalid).is_err());
+
+        // constructor checks fail rather than panic
+        for invalid in [
+            r#"{"type": "LogUniform", "lower": 0.0, "upper": 1.0}"#,
+            r#"{"type": "LogNormal", "median": 1.0, "geometric_sd": 0.5}"#,
+            r#"{"type": "Truncated", "distribution": {"type": "Normal", "mean": 0.0, "std_dev": 1.0},
+                "lower": 2.0, "upper": 1.0}"#,
+            r#"{"type": "SpikeAndSlab", "inclusion_probability": 0.5, "spike_scale": -1.0,
+                "slab": {"type": "LogUniform", "lower": 1.0, "upper": 2.0}}"#,
+        ] {
+            let spec: PartialPriorSpec = serde_json::from_str(invalid).unwrap();
+            assert!(matches!(spec.build(), Err(PriorError::Invalid(_))));
+            assert!(serde_json::from_str::<Box<dyn PartialPrior>>(invalid).is_err());
+        }
+        for invalid in [
+            r#"{"type": "MultivariateNormal", "mean": [0.0, 0.0],
+                "covariance": [[1.0, 2.0], [2.0, 1.0]]}"#,
+            r#"{"type": "Dirichlet", "alpha": [1.0]}"#,
+        ] {
+            assert!(serde_json::from_str::<Box<dyn Prior>>(invalid).is_err());
+        }
+        assert!(serde_json::from_str::<CategoricalPrior>(r#"{"log_probabilities": []}"#).is_err());
+    }
+
+    #[test]
+    fn prior_builder() {
+        let names = vec!["slope".to_string(), "intercept".to_string()];
+        let prior = PriorBuilder::new()
+            .with(
+                "intercept",
+                IndependentPrior {
+                    distribution: Uniform::new(0.0, 1.0).unwrap(),
+                },
+            )
+            .with(
+                "slope",
+                IndependentPrior {
+                    distribution: Uniform::new(10.0, 20.0).unwrap(),
+                },
+            )
+            .build(&names)
+            .unwrap();
+        assert!(prior.logprobability(&Guess::new(&[15.0, 0.5])).is_finite());
+        assert_eq!(
+            prior.logprobability(&Guess::new(&[0.5, 15.0])),
+            f64::NEG_INFINITY
+        );
+
+        let uniform = || IndependentPrior {
+            distribution: Uniform::new(0.0, 1.0).unwrap(),
+        };
+        let missing = PriorBuilder::new().with("slope", uniform()).build(&names);
+        assert_eq!(
+            missing.err(),
+            Some(PriorError::Missing("intercept".to_string()))
+        );
+        let duplicate = PriorBuilder::new()
+            .with("slope", uniform())
+            .with("slope", uniform())
+            .build(&names);
+        assert_eq!(
+            duplicate.err(),
+            Some(PriorError::Duplicate("slope".to_string()))
+        );
+        let unknown = PriorBuilder::new().with("slop", uniform()).build(&names);
+        let err = unknown.err().unwrap();
+        assert_eq!(err, PriorError::Unknown("slop".to_string()));
+        assert_eq!(err.to_string(), "prior given for unknown parameter 'slop'");
     }
 }
@@ -0,0 +1,505 @@
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub trait Transform {
+    /// Map a point in the sampler's unconstrained space to parameter space.
+    fn forward(&self, unconstrained: &[f64]) -> Vec<f64>;
+
+    /// Map a point in parameter space back to the sampler's space.
+    fn inverse(&self, constrained: &[f64]) -> Vec<f64>;
+
+    /// Log absolute determinant of the Jacobian of `forward`.
+    fn log_jacobian(&self, unconstrained: &[f64]) -> f64;
+
+    fn unconstrained_dimension(&self) -> usize;
+
+    fn constrained_dimension(&self) -> usize;
+
+    /// Serializable description of this transform, if it is a built-in one.
+    fn spec(&self) -> Option<TransformSpec> {
+        None
+    }
+}
+
+/// Tagged description of the built-in transforms, used to save and reload
+/// them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", try_from = "TransformSpecRaw")]
+pub enum TransformSpec {
+    Identity { dimension: usize },
+    Simplex { dimension: usize },
+    Parameters { bijectors: Vec<Bijector> },
+    Blocks { blocks: Vec<TransformSpec> },
+}
+
+impl TransformSpec {
+    pub fn build(self) -> Result<Box<dyn Transform>, String> {
+        Ok(match self {
+            TransformSpec::Identity { dimension } => Box::new(Identity::new(dimension)),
+            TransformSpec::Simplex { dimension } => Box::new(SimplexTransform::try_new(dimension)?),
+            TransformSpec::Parameters { bijectors } => {
+                Box::new(ParameterTransform::try_new(bijectors)?)
+            }
+            TransformSpec::Blocks { blocks } => Box::new(BlockTransform::new(
+                blocks
+                    .into_iter()
+                    .map(TransformSpec::build)
+                    .collect::<Result<_, _>>()?,
+            )),
+        })
+    }
+}
+
+/// Unchecked `TransformSpec` as read from JSON.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum TransformSpecRaw {
+    Identity { dimension: usize },
+    Simplex { dimension: usize },
+    Parameters { bijectors: Vec<Bijector> },
+    Blocks { blocks: Vec<TransformSpec> },
+}
+
+impl TryFrom<TransformSpecRaw> for TransformSpec {
+    type Error = String;
+
+    fn try_from(raw: TransformSpecRaw) -> Result<Self, String> {
+        match raw {
+            TransformSpecRaw::Identity { dimension } => Ok(TransformSpec::Identity { dimension }),
+            TransformSpecRaw::Simplex { dimension } => {
+                SimplexTransform::try_new(dimension)?;
+                Ok(TransformSpec::Simplex { dimension })
+            }
+            TransformSpecRaw::Parameters { bijectors } => {
+                for b in bijectors.iter() {
+                    b.check()?;
+                }
+                Ok(TransformSpec::Parameters { bijectors })
+            }
+            // blocks are checked as they are read
+            TransformSpecRaw::Blocks { blocks } => Ok(TransformSpec::Blocks { blocks }),
+        }
+    }
+}
+
+impl Serialize for dyn Transform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spec()
+            .ok_or_else(|| S::Error::custom("transform has no serializable description"))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Transform> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        TransformSpec::deserialize(deserializer)?
+            .build()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Leaves `dimension` parameters untouched.
+pub struct Identity {
+    dimension: usize,
+}
+
+impl Identity {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+}
+
+impl Transform for Identity {
+    fn forward(&self, unconstrained: &[f64]) -> Vec<f64> {
+        unconstrained.to_vec()
+    }
+
+    fn inverse(&self, constrained: &[f64]) -> Vec<f64> {
+        constrained.to_vec()
+    }
+
+    fn log_jacobian(&self, _unconstrained: &[f64]) -> f64 {
+        0.0
+    }
+
+    fn unconstrained_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn constrained_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn spec(&self) -> Option<TransformSpec> {
+        Some(TransformSpec::Identity {
+            dimension: self.dimension,
+        })
+    }
+}
+
+/// Maps `dimension - 1` unconstrained values onto `dimension` fractions that
+/// are positive and sum to one, using the additive log-ratio with the last
+/// fraction as reference.
+pub struct SimplexTransform {
+    dimension: usize,
+}
+
+impl SimplexTransform {
+    pub fn new(dimension: usize) -> Self {
+        Self::try_new(dimension).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(dimension: usize) -> Result<Self, String> {
+        if dimension < 2 {
+            return Err("a simplex needs at least two fractions".to_string());
+        }
+        Ok(Self { dimension })
+    }
+}
+
+impl Transform for SimplexTransform {
+    fn forward(&self, unconstrained: &[f64]) -> Vec<f64> {
+        // shift by the largest log-ratio (or the reference's zero) for stability
+        let max = unconstrained.iter().cloned().fold(0.0, f64::max);
+        let mut fractions: Vec<f64> = unconstrained.iter().map(|y| (y - max).exp()).collect();
+        fractions.push((-max).exp());
+        let total: f64 = fractions.iter().sum();
+        fractions.iter().map(|x| x / total).collect()
+    }
+
+    fn inverse(&self, constrained: &[f64]) -> Vec<f64> {
+        let reference = constrained[self.dimension - 1].ln();
+        constrained[..self.dimension - 1]
+            .iter()
+            .map(|x| x.ln() - reference)
+            .collect()
+    }
+
+    fn log_jacobian(&self, unconstrained: &[f64]) -> f64 {
+        self.forward(unconstrained).iter().map(|x| x.ln()).sum()
+    }
+
+    fn unconstrained_dimension(&self) -> usize {
+        self.dimension - 1
+    }
+
+    fn constrained_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn spec(&self) -> Option<TransformSpec> {
+        Some(TransformSpec::Simplex {
+            dimension: self.dimension,
+        })
+    }
+}
+
+/// Invertible map for a single parameter, from the real line onto the
+/// parameter's support.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Bijector {
+    Identity,
+    /// `x = exp(y)` for positive parameters.
+    Log,
+    /// `x = lower + (upper - lower) / (1 + exp(-y))` for bounded parameters.
+    Logit {
+        lower: f64,
+        upper: f64,
+    },
+    /// `x = ln(1 + exp(y))` for positive parameters, linear for large `y`.
+    Softplus,
+    /// `x = scale * y + shift`, e.g. to sample on a unit scale.
+    Affine {
+        scale: f64,
+        shift: f64,
+    },
+}
+
+fn softplus(y: f64) -> f64 {
+    y.max(0.0) + (-y.abs()).exp().ln_1p()
+}
+
+impl Bijector {
+    /// Fails unless the map is invertible: a `Logit` needs finite bounds
+    /// with `lower < upper` and an `Affine` a finite, non-zero scale.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            Bijector::Logit { lower, upper }
+                if !(lower.is_finite() && upper.is_finite() && lower < upper) =>
+            {
+                Err("logit bounds must be finite with lower below upper".to_string())
+            }
+            Bijector::Affine { scale, shift }
+                if !(scale.is_finite() && *scale != 0.0 && shift.is_finite()) =>
+            {
+                Err("affine scale must be finite and non-zero".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn forward(&self, y: f64) -> f64 {
+        match self {
+            Bijector::Identity => y,
+            Bijector::Log => y.exp(),
+            Bijector::Logit { lower, upper } => lower + (upper - lower) / (1.0 + (-y).exp()),
+            Bijector::Softplus => softplus(y),
+            Bijector::Affine { scale, shift } => scale * y + shift,
+        }
+    }
+
+    pub fn inverse(&self, x: f64) -> f64 {
+        match self {
+            Bijector::Identity => x,
+            Bijector::Log => x.ln(),
+            Bijector::Logit { lower, upper } => ((x - lower) / (upper - x)).ln(),
+            Bijector::Softplus => x + (-(-x).exp()).ln_1p(),
+            Bijector::Affine { scale, shift } => (x - shift) / scale,
+        }
+    }
+
+    pub fn log_jacobian(&self, y: f64) -> f64 {
+        match self {
+            Bijector::Identity => 0.0,
+            Bijector::Log => y,
+            Bijector::Logit { lower, upper } => (upper - lower).ln() - softplus(-y) - softplus(y),
+            Bijector::Softplus => -softplus(-y),
+            Bijector::Affine { scale, .. } => scale.abs().ln(),
+        }
+    }
+}
+
+/// One `Bijector` per parameter.
+pub struct ParameterTransform {
+    bijectors: Vec<Bijector>,
+}
+
+impl ParameterTransform {
+    pub fn new(bijectors: Vec<Bijector>) -> Self {
+        Self::try_new(bijectors).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(bijectors: Vec<Bijector>) -> Result<Self, String> {
+        for b in bijectors.iter() {
+            b.check()?;
+        }
+        Ok(Self { bijectors })
+    }
+}
+
+impl Transform for ParameterTransform {
+    fn forward(&self, unconstrained: &[f64]) -> Vec<f64> {
+        self.bijectors
+            .iter()
+            .zip(unconstrained)
+            .map(|(b, y)| b.forward(*y))
+            .collect()
+    }
+
+    fn inverse(&self, constrained: &[f64]) -> Vec<f64> {
+        self.bijectors
+            .iter()
+            .zip(constrained)
+            .map(|(b, x)| b.inverse(*x))
+            .collect()
+    }
+
+    fn log_jacobian(&self, unconstrained: &[f64]) -> f64 {
+        self.bijectors
+            .iter()
+            .zip(unconstrained)
+            .map(|(b, y)| b.log_jacobian(*y))
+            .sum()
+    }
+
+    fn unconstrained_dimension(&self) -> usize {
+        self.bijectors.len()
+    }
+
+    fn constrained_dimension(&self) -> usize {
+        self.bijectors.len()
+    }
+
+    fn spec(&self) -> Option<TransformSpec> {
+        Some(TransformSpec::Parameters {
+            bijectors: self.bijectors.clone(),
+        })
+    }
+}
+
+/// Applies each transform to consecutive blocks of parameters in order.
+pub struct BlockTransform {
+    blocks: Vec<Box<dyn Transform>>,
+}
+
+impl BlockTransform {
+    pub fn new(blocks: Vec<Box<dyn Transform>>) -> Self {
+        Self { blocks }
+    }
+}
+
+impl Transform for BlockTransform {
+    fn forward(&self, unconstrained: &[f64]) -> Vec<f64> {
+        let mut start = 0;
+        let mut out = Vec::with_capacity(self.constrained_dimension());
+        for block in self.blocks.iter() {
+            let end = start + block.unconstrained_dimension();
+            out.extend(block.forward(&unconstrained[start..end]));
+            start = end;
+        }
+        out
+    }
+
+    fn inverse(&self, constrained: &[f64]) -> Vec<f64> {
+        let mut start = 0;
+        let mut out = Vec::with_capacity(self.unconstrained_dimension());
+        for block in self.blocks.iter() {
+            let end = start + block.constrained_dimension();
+            out.extend(block.inverse(&constrained[start..end]));
+            start = end;
+        }
+        out
+    }
+
+    fn log_jacobian(&self, unconstrained: &[f64]) -> f64 {
+        let mut start = 0;
+        let mut lj = 0.0;
+        for block in self.blocks.iter() {
+            let end = start + block.unconstrained_dimension();
+            lj += block.log_jacobian(&unconstrained[start..end]);
+            start = end;
+        }
+        lj
+    }
+
+    fn unconstrained_dimension(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|b| b.unconstrained_dimension())
+            .sum()
+    }
+
+    fn constrained_dimension(&self) -> usize {
+        self.blocks.iter().map(|b| b.constrained_dimension()).sum()
+    }
+
+    fn spec(&self) -> Option<TransformSpec> {
+        Some(TransformSpec::Blocks {
+            blocks: self
+                .blocks
+                .iter()
+                .map(|b| b.spec())
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statrs::assert_almost_eq;
+
+    #[test]
+    fn simplex_round_trip() {
+        let transform = SimplexTransform::new(3);
+        let fractions = vec![0.2, 0.3, 0.5];
+        let y = transform.inverse(&fractions);
+        assert_eq!(y.len(), 2);
+        for (a, b) in transform.forward(&y).iter().zip(&fractions) {
+            assert_almost_eq!(*a, *b, 1e-12);
+        }
+        let extreme = transform.forward(&[800.0, -800.0]);
+        assert_almost_eq!(extreme.iter().sum::<f64>(), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn simplex_jacobian() {
+        // compare with a finite-difference determinant for two free values
+        let transform = SimplexTransform::new(3);
+        let y = [0.3, -0.7];
+        let h = 1e-6;
+        let d = |i: usize, j: usize| {
+            let mut up = y;
+            let mut down = y;
+            up[j] += h;
+            down[j] -= h;
+            (transform.forward(&up)[i] - transform.forward(&down)[i]) / (2.0 * h)
+        };
+        let det = d(0, 0) * d(1, 1) - d(0, 1) * d(1, 0);
+        assert_almost_eq!(transform.log_jacobian(&y), det.abs().ln(), 1e-6);
+    }
+
+    #[test]
+    fn block_transform() {
+        let transform = BlockTransform::new(vec![
+            Box::new(Identity::new(1)),
+            Box::new(SimplexTransform::new(2)),
+        ]);
+        assert_eq!(transform.unconstrained_dimension(), 2);
+        assert_eq!(transform.constrained_dimension(), 3);
+        let x = transform.forward(&[5.0, 0.0]);
+        assert_eq!(x, vec![5.0, 0.5, 0.5]);
+        assert_eq!(transform.inverse(&x), vec![5.0, 0.0]);
+        assert_almost_eq!(
+            transform.log_jacobian(&[5.0, 0.0]),
+            2.0 * 0.5_f64.ln(),
+            1e-12
+        );
+    }
+
+    #[test]
+    fn bijectors() {
+        let bijectors = [
+            Bijector::Identity,
+            Bijector::Log,
+            Bijector::Logit {
+                lower: 10.0,
+                upper: 200.0,
+            },
+            Bijector::Softplus,
+            Bijector::Affine {
+                scale: -2.0,
+                shift: 3.0,
+            },
+        ];
+        let h = 1e-6;
+        for b in bijectors.iter() {
+            for y in [-3.0, 0.0, 0.7, 4.0] {
+                assert_almost_eq!(b.inverse(b.forward(y)), y, 1e-8);
+                let derivative = (b.forward(y + h) - b.forward(y - h)) / (2.0 * h);
+                assert_almost_eq!(b.log_jacobian(y), derivative.abs().ln(), 1e-5);
+            }
+        }
+        let logit = bijectors[2];
+        assert!(logit.forward(-800.0) >= 10.0 && logit.forward(800.0) <= 200.0);
+        assert_almost_eq!(Bijector::Softplus.forward(800.0), 800.0, 1e-9);
+        assert!(Bijector::Softplus.log_jacobian(-800.0).is_finite());
+    }
+
+    #[test]
+    fn invalid_transform_json() {
+        for invalid in [
+            r#"{"type": "Simplex", "dimension": 1}"#,
+            r#"{"type": "Parameters", "bijectors": [{"Logit": {"lower": 2.0, "upper": 1.0}}]}"#,
+            r#"{"type": "Parameters", "bijectors": [{"Affine": {"scale": 0.0, "shift": 1.0}}]}"#,
+            r#"{"type": "Blocks", "blocks": [{"type": "Simplex", "dimension": 0}]}"#,
+        ] {
+            assert!(serde_json::from_str::<TransformSpec>(invalid).is_err());
+            assert!(serde_json::from_str::<Box<dyn Transform>>(invalid).is_err());
+        }
+        let valid = r#"{"type": "Blocks", "blocks": [{"type": "Simplex", "dimension": 3},
+            {"type": "Parameters", "bijectors": ["Log", {"Logit": {"lower": 0.0, "upper": 1.0}}]}]}"#;
+        let transform: Box<dyn Transform> = serde_json::from_str(valid).unwrap();
+        assert_eq!(transform.constrained_dimension(), 5);
+        assert!(TransformSpec::Simplex { dimension: 1 }.build().is_err());
+    }
+
+    #[test]
+    fn parameter_transform() {
+        let transform = ParameterTransform::new(vec![Bijector::Identity, Bijector::Log]);
+        assert_eq!(transform.forward(&[1.0, 0.0]), vec![1.0, 1.0]);
+        assert_eq!(transform.inverse(&[1.0, 1.0]), vec![1.0, 0.0]);
+        assert_eq!(transform.log_jacobian(&[1.0, 2.0]), 2.0);
+    }
+}